@@ -1,24 +1,60 @@
 #version 330 core
 in vec2 oTex;
 in vec3 oNor;
+in vec3 oPos;
 
 out vec4 FragColor;
 
-uniform sampler2D tex;
-uniform bool rat;
+uniform sampler2DArray tex;
+uniform int layer;
 uniform bool shaded;
-uniform vec3 color;
 uniform int tiling;
+// sprite sheets: columns and rows, and which cell to draw
+uniform vec2 sheet = vec2(1.0);
+uniform vec2 frame = vec2(0.0);
+uniform bool mirror;
+
+struct Material {
+    vec3 diffuse;
+    vec3 specular;
+    float shininess;
+    vec3 emissive;
+};
+uniform Material material;
+uniform vec3 viewPos;
+
+// a single white light, far away like the sun
+const vec3 LIGHT_DIR = vec3(1.0, 1.0, -1.0);
+const float AMBIENT = 0.15;
+
+// texels more transparent than this don't even write depth
+const float ALPHA_CUTOFF = 0.1;
 
 void main() {
     if (shaded) {
-        vec3 lightDir = vec3(1.0, 1.0, -1.0);
-        float diffuse = max(dot(oNor, lightDir), 0.2);
-        FragColor = vec4(color * diffuse * 0.2, 0.0);
+        // Phong: ambient + diffuse + specular, plus its own glow
+        vec3 normal = normalize(oNor);
+        vec3 lightDir = normalize(LIGHT_DIR);
+        vec3 viewDir = normalize(viewPos - oPos);
+        vec3 reflectDir = reflect(-lightDir, normal);
+
+        float diffuse = max(dot(normal, lightDir), 0.0);
+        float specular = diffuse > 0.0
+            ? pow(max(dot(viewDir, reflectDir), 0.0), material.shininess)
+            : 0.0;
+
+        vec3 color = material.diffuse * (AMBIENT + diffuse)
+                   + material.specular * specular
+                   + material.emissive;
+        FragColor = vec4(color, 1.0);
     } else {
-        vec4 color = texture(tex, oTex * tiling);
-        // if rat and pure green, discard
-        if (rat && color.rgb == vec3(0.0, 1.0, 0.0)) {
+        vec2 uv = oTex;
+        if (mirror) {
+            uv.x = 1.0 - uv.x;
+        }
+        uv = (frame + uv) / sheet;
+        vec4 color = texture(tex, vec3(uv * tiling, layer));
+        if (color.a < ALPHA_CUTOFF) {
             discard;
         }
         FragColor = color;
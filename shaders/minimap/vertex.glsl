@@ -0,0 +1,13 @@
+#version 330 core
+layout (location = 0) in vec2 aPos;
+layout (location = 1) in vec4 aColor;
+
+out vec4 oColor;
+
+// from maze cells to the corner of the screen the map is in
+uniform mat4 transform;
+
+void main() {
+    gl_Position = transform * vec4(aPos, 0.0, 1.0);
+    oColor = aColor;
+}
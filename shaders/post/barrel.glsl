@@ -0,0 +1,22 @@
+#version 330 core
+in vec2 oTex;
+
+out vec4 FragColor;
+
+uniform sampler2D screen;
+
+// amount of bulging, 0.0 is a flat screen
+uniform float strength = 0.15;
+
+void main() {
+    vec2 centered = oTex - vec2(0.5);
+    float r2 = dot(centered, centered);
+    vec2 uv = vec2(0.5) + centered * (1.0 + strength * r2);
+
+    // outside the tube
+    if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+        FragColor = vec4(0.0, 0.0, 0.0, 1.0);
+    } else {
+        FragColor = texture(screen, uv);
+    }
+}
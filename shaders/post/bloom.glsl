@@ -0,0 +1,35 @@
+#version 330 core
+in vec2 oTex;
+
+out vec4 FragColor;
+
+uniform sampler2D screen;
+uniform vec2 resolution;
+
+// brightness above which pixels glow
+uniform float threshold = 0.7;
+// how much glow is added back
+uniform float strength = 0.8;
+// distance between blur samples, in pixels
+uniform float spread = 3.0;
+
+const int SAMPLES = 4;
+
+void main() {
+    vec3 color = texture(screen, oTex).rgb;
+    vec2 step = spread / resolution;
+
+    // single pass gaussian-ish blur of the bright parts
+    vec3 glow = vec3(0.0);
+    float total = 0.0;
+    for (int x = -SAMPLES; x <= SAMPLES; x++) {
+        for (int y = -SAMPLES; y <= SAMPLES; y++) {
+            float w = exp(-float(x * x + y * y) / float(SAMPLES * SAMPLES));
+            vec3 s = texture(screen, oTex + vec2(x, y) * step).rgb;
+            glow += max(s - vec3(threshold), vec3(0.0)) * w;
+            total += w;
+        }
+    }
+
+    FragColor = vec4(color + strength * glow / total, 1.0);
+}
@@ -0,0 +1,21 @@
+#version 330 core
+in vec2 oTex;
+
+out vec4 FragColor;
+
+uniform sampler2D screen;
+uniform vec2 resolution;
+
+// channel separation at the corners, in pixels
+uniform float amount = 3.0;
+
+void main() {
+    // grows towards the edges like a cheap lens
+    vec2 offset = (oTex - vec2(0.5)) * 2.0 * amount / resolution;
+
+    float r = texture(screen, oTex + offset).r;
+    float g = texture(screen, oTex).g;
+    float b = texture(screen, oTex - offset).b;
+
+    FragColor = vec4(r, g, b, 1.0);
+}
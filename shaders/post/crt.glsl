@@ -0,0 +1,25 @@
+#version 330 core
+in vec2 oTex;
+
+out vec4 FragColor;
+
+uniform sampler2D screen;
+uniform vec2 resolution;
+uniform float time;
+
+// how dark the gaps between scanlines get
+uniform float intensity = 0.3;
+// scanlines per screen pixel row
+uniform float density = 0.5;
+// slow brightness wobble, like an old tube
+uniform float flicker = 0.02;
+
+void main() {
+    vec3 color = texture(screen, oTex).rgb;
+
+    float line = sin(oTex.y * resolution.y * density * 3.14159265);
+    color *= 1.0 - intensity * (0.5 - 0.5 * line);
+    color *= 1.0 - flicker * (0.5 + 0.5 * sin(time * 60.0));
+
+    FragColor = vec4(color, 1.0);
+}
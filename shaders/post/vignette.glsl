@@ -0,0 +1,23 @@
+#version 330 core
+in vec2 oTex;
+
+out vec4 FragColor;
+
+uniform sampler2D screen;
+
+// how dark the corners get
+uniform float strength = 0.6;
+// distance from the center where darkening starts
+uniform float radius = 0.75;
+// width of the transition
+uniform float softness = 0.45;
+
+void main() {
+    vec3 color = texture(screen, oTex).rgb;
+
+    float d = distance(oTex, vec2(0.5));
+    float v = smoothstep(radius, radius - softness, d);
+    color *= mix(1.0, v, strength);
+
+    FragColor = vec4(color, 1.0);
+}
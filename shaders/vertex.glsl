@@ -5,14 +5,15 @@ layout (location = 2) in vec3 aNor;
 
 out vec2 oTex;
 out vec3 oNor;
+out vec3 oPos;
 
-uniform bool rat;
+uniform bool billboard;
 uniform mat4 model;
 uniform mat4 view;
 uniform mat4 proj;
 
 void main() {
-    if (rat) {
+    if (billboard) {
         // reset rotation part of the model view matrix
         mat4 mv = mat4(1.0);
         mat4 tmp = view * model;
@@ -22,6 +23,8 @@ void main() {
         gl_Position = proj * view * model * vec4(aPos, 1.0);
     }
     oTex = aTex;
+    // world position, for the specular highlights
+    oPos = vec3(model * vec4(aPos, 1.0));
     // normal matrix
     oNor = mat3(transpose(inverse(model))) * aNor;
 }
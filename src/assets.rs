@@ -0,0 +1,86 @@
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use image::{self, DynamicImage};
+
+use error::Error;
+
+// default shaders, textures and meshes, compiled into the binary
+// so it works no matter where it's run from
+const EMBEDDED: &[(&str, &[u8])] = &[
+    ("shaders/vertex.glsl", include_bytes!("../shaders/vertex.glsl")),
+    ("shaders/fragment.glsl", include_bytes!("../shaders/fragment.glsl")),
+    ("shaders/post/vertex.glsl", include_bytes!("../shaders/post/vertex.glsl")),
+    ("shaders/post/crt.glsl", include_bytes!("../shaders/post/crt.glsl")),
+    ("shaders/post/barrel.glsl", include_bytes!("../shaders/post/barrel.glsl")),
+    ("shaders/post/vignette.glsl", include_bytes!("../shaders/post/vignette.glsl")),
+    ("shaders/post/chromatic.glsl", include_bytes!("../shaders/post/chromatic.glsl")),
+    ("shaders/post/bloom.glsl", include_bytes!("../shaders/post/bloom.glsl")),
+    ("shaders/minimap/vertex.glsl", include_bytes!("../shaders/minimap/vertex.glsl")),
+    ("shaders/minimap/fragment.glsl", include_bytes!("../shaders/minimap/fragment.glsl")),
+    ("themes/classic/theme.txt", include_bytes!("../themes/classic/theme.txt")),
+    ("themes/hedge/theme.txt", include_bytes!("../themes/hedge/theme.txt")),
+    ("themes/office/theme.txt", include_bytes!("../themes/office/theme.txt")),
+    ("resources/brick.bmp", include_bytes!("../resources/brick.bmp")),
+    ("resources/thing.bmp", include_bytes!("../resources/thing.bmp")),
+    ("resources/ceiling.bmp", include_bytes!("../resources/ceiling.bmp")),
+    ("resources/floor.bmp", include_bytes!("../resources/floor.bmp")),
+    ("resources/rat.bmp", include_bytes!("../resources/rat.bmp")),
+    ("meshes/icosahedron.dae", include_bytes!("../meshes/icosahedron.dae")),
+];
+
+/// Finds assets by their relative name, like `shaders/vertex.glsl`,
+/// first in the override directory and then in the embedded ones.
+#[derive(Debug, Clone)]
+pub struct Assets {
+    dir: Option<PathBuf>
+}
+
+impl Assets {
+    pub fn new(dir: Option<PathBuf>) -> Assets {
+        Assets {
+            dir: dir
+        }
+    }
+
+    /// The file on disk overriding `name`, if there is one.
+    pub fn path(&self, name: &str) -> Option<PathBuf> {
+        self.dir.as_ref()
+            .map(|dir| dir.join(name))
+            .filter(|path| path.is_file())
+    }
+
+    pub fn read(&self, name: &str) -> Result<Cow<'static, [u8]>, Error> {
+        let io_error = |err| Error::Io { path: name.to_string(), err: err };
+
+        if let Some(path) = self.path(name) {
+            let mut bytes = Vec::new();
+            File::open(&path)
+                .and_then(|mut file| file.read_to_end(&mut bytes))
+                .map_err(&io_error)?;
+            return Ok(Cow::Owned(bytes));
+        }
+
+        EMBEDDED.iter()
+            .find(|&&(embedded, _)| embedded == name)
+            .map(|&(_, bytes)| Cow::Borrowed(bytes))
+            .ok_or_else(|| io_error(io::Error::new(io::ErrorKind::NotFound,
+                                                   "no such asset")))
+    }
+
+    pub fn read_to_string(&self, name: &str) -> Result<String, Error> {
+        let bytes = self.read(name)?;
+        String::from_utf8(bytes.into_owned()).map_err(|_| Error::Io {
+            path: name.to_string(),
+            err: io::Error::new(io::ErrorKind::InvalidData, "not valid UTF-8")
+        })
+    }
+
+    pub fn image(&self, name: &str) -> Result<DynamicImage, Error> {
+        let bytes = self.read(name)?;
+        image::load_from_memory(&bytes)
+            .map_err(|err| Error::Image { path: name.to_string(), err: err })
+    }
+}
@@ -0,0 +1,177 @@
+use rand::Rng;
+
+use walker::Direction;
+
+// rats closer than this to the camera, in steps, run away
+const FLEE_DISTANCE: usize = 3;
+// chance of starting to follow the camera at each cell
+const FOLLOW_CHANCE: f32 = 0.05;
+// and for how many cells
+const FOLLOW_CELLS: u32 = 8;
+
+/// What a rat is up to, decided at every cell. Nothing here
+/// touches GL, so it can be simulated on its own.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Behaviour {
+    /// Random walk, never turning back unless at a dead end.
+    Wander,
+    /// Away from the camera, through the corridors, until cornered.
+    Flee,
+    /// Towards the camera, for a number of cells.
+    Follow(u32)
+}
+
+/// What a rat knows about the rest of the maze.
+#[derive(Debug)]
+pub struct Surroundings<'a> {
+    /// steps from the camera to every cell, indexed like `Maze::grid`
+    pub distances: &'a [Vec<usize>],
+    /// cells where the rats are or are heading to
+    pub rats: &'a [(usize, usize)]
+}
+
+impl Behaviour {
+    /// The behaviour for the next cell, `distance` steps away from the camera.
+    pub fn next<R: Rng>(self, distance: usize, rng: &mut R) -> Behaviour {
+        if distance <= FLEE_DISTANCE {
+            return Behaviour::Flee;
+        }
+
+        match self {
+            Behaviour::Follow(cells) if cells > 1 => Behaviour::Follow(cells - 1),
+            _ if rng.next_f32() < FOLLOW_CHANCE => Behaviour::Follow(FOLLOW_CELLS),
+            _ => Behaviour::Wander
+        }
+    }
+
+    /// Where to go from a cell, `options` being its open directions and
+    /// the cells they lead to. Cells with other rats are avoided if possible.
+    pub fn choose<R: Rng>(&self,
+                          heading: Direction,
+                          options: &[(Direction, (usize, usize))],
+                          surroundings: &Surroundings,
+                          rng: &mut R) -> Direction {
+        let free: Vec<(Direction, (usize, usize))> = options.iter()
+            .filter(|&&(_, cell)| !surroundings.rats.contains(&cell))
+            .cloned()
+            .collect();
+        let mut candidates = if free.is_empty() { options.to_vec() } else { free };
+
+        // shuffled so ties are broken at random
+        rng.shuffle(&mut candidates);
+        let distance = |&(_, (i, j)): &(Direction, (usize, usize))| surroundings.distances[i][j];
+
+        // turning back only at dead ends, or fleeing would just
+        // bounce between the end of a corridor and the cell before
+        let onwards: Vec<_> = candidates.iter()
+            .filter(|&&(d, _)| d != heading.opposite())
+            .cloned()
+            .collect();
+        let onwards = if onwards.is_empty() { &candidates } else { &onwards };
+
+        let choice = match *self {
+            Behaviour::Wander => onwards.first().cloned(),
+            Behaviour::Flee => onwards.iter().max_by_key(|c| distance(c)).cloned(),
+            Behaviour::Follow(_) => candidates.iter().min_by_key(|c| distance(c)).cloned()
+        };
+
+        choice.map(|(d, _)| d).unwrap_or(heading)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, XorShiftRng};
+
+    use maze::Maze;
+    use walker::{Direction, Walker};
+    use super::{Behaviour, Surroundings, FOLLOW_CELLS};
+
+    // a crossing at (1, 1), the camera at its west end:
+    //       (0,1)
+    // (1,0) (1,1) (1,2)
+    //       (2,1)
+    fn crossing() -> Maze {
+        let mut maze = Maze::closed(3, 3);
+        maze.connect((1, 1), (0, 1));
+        maze.connect((1, 1), (1, 0));
+        maze.connect((1, 1), (1, 2));
+        maze.connect((1, 1), (2, 1));
+        maze
+    }
+
+    const CAMERA: (usize, usize) = (1, 0);
+
+    fn options(walker: &Walker) -> Vec<(Direction, (usize, usize))> {
+        walker.options().into_iter().map(|d| (d, walker.ahead(d))).collect()
+    }
+
+    fn rng(seed: u32) -> XorShiftRng {
+        XorShiftRng::from_seed([seed, 2, 3, 4])
+    }
+
+    #[test]
+    fn flee_moves_away_from_the_camera() {
+        let maze = crossing();
+        let distances = maze.distances(CAMERA.0, CAMERA.1);
+        let surroundings = Surroundings { distances: &distances, rats: &[] };
+        // came in from the north
+        let walker = Walker::new(&maze, 1, 1);
+
+        for seed in 1..50 {
+            let d = Behaviour::Flee.choose(Direction::South, &options(&walker),
+                                           &surroundings, &mut rng(seed));
+            let (i, j) = walker.ahead(d);
+            assert!(distances[i][j] > distances[1][1], "fled {:?}", d);
+        }
+    }
+
+    #[test]
+    fn follow_moves_closer_to_the_camera() {
+        let maze = crossing();
+        let distances = maze.distances(CAMERA.0, CAMERA.1);
+        let surroundings = Surroundings { distances: &distances, rats: &[] };
+        let walker = Walker::new(&maze, 1, 1);
+
+        for seed in 1..50 {
+            let d = Behaviour::Follow(3).choose(Direction::South, &options(&walker),
+                                                &surroundings, &mut rng(seed));
+            assert_eq!(walker.ahead(d), CAMERA);
+        }
+    }
+
+    #[test]
+    fn follow_ends_after_its_cells() {
+        let mut rng = rng(1);
+        let far = 10;
+
+        let mut behaviour = Behaviour::Follow(FOLLOW_CELLS);
+        for cells in (1..FOLLOW_CELLS).rev() {
+            behaviour = behaviour.next(far, &mut rng);
+            assert_eq!(behaviour, Behaviour::Follow(cells));
+        }
+
+        // done, unless it happens to start following again
+        let after = behaviour.next(far, &mut rng);
+        assert!(after == Behaviour::Wander || after == Behaviour::Follow(FOLLOW_CELLS),
+                "{:?}", after);
+    }
+
+    #[test]
+    fn choose_avoids_cells_with_rats() {
+        let maze = crossing();
+        let distances = maze.distances(CAMERA.0, CAMERA.1);
+        let walker = Walker::new(&maze, 1, 1);
+        // the best cell to flee to and one of the others are taken
+        let rats = [(1, 2), (2, 1)];
+        let surroundings = Surroundings { distances: &distances, rats: &rats };
+
+        for seed in 1..50 {
+            for behaviour in &[Behaviour::Wander, Behaviour::Flee, Behaviour::Follow(3)] {
+                let d = behaviour.choose(Direction::South, &options(&walker),
+                                         &surroundings, &mut rng(seed));
+                assert!(!rats.contains(&walker.ahead(d)), "{:?} went {:?}", behaviour, d);
+            }
+        }
+    }
+}
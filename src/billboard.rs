@@ -0,0 +1,95 @@
+use std::mem;
+use std::ptr;
+use std::ffi::CStr;
+
+use gl;
+use gl::types::*;
+
+use texture::{TextureArray, TexType};
+use shader::Shader;
+
+// texture coordinates are weird because somehow
+// images are flipped on the x axis
+const VERTICES: [f32; 20] = [
+     0.5, -0.5, 0.0,  0.0, 1.0, // bottom right
+    -0.5, -0.5, 0.0,  1.0, 1.0, // bottom left
+    -0.5,  0.5, 0.0,  1.0, 0.0, // top left
+     0.5,  0.5, 0.0,  0.0, 0.0, // top right
+];
+const INDICES: [u32; 6] = [
+    0, 1, 3,
+    1, 2, 3
+];
+
+/// A unit quad the vertex shader turns to face the camera,
+/// shared by everything drawn as a sprite.
+#[derive(Debug)]
+pub struct Billboard {
+    vao: GLuint
+}
+
+impl Billboard {
+
+    pub unsafe fn new() -> Billboard {
+
+        let (mut vao, mut vbo, mut ebo) = (0, 0, 0);
+
+        //* generate VAO, VBO, EBO
+        gl::GenVertexArrays(1, &mut vao);
+        gl::GenBuffers(1, &mut vbo);
+        gl::GenBuffers(1, &mut ebo);
+
+        //* binding VAO
+        gl::BindVertexArray(vao);
+
+        //* VBO data
+        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+        gl::BufferData(gl::ARRAY_BUFFER,
+                       mem::size_of::<[f32; 20]>() as isize,
+                       VERTICES.as_ptr() as *const _,
+                       gl::STATIC_DRAW);
+
+        //* EBO data
+        gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
+        gl::BufferData(gl::ELEMENT_ARRAY_BUFFER,
+                       mem::size_of::<[u32; 6]>() as isize,
+                       INDICES.as_ptr() as *const _,
+                       gl::STATIC_DRAW);
+
+        //* vertex attribs
+        // aPos = 0
+        gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE,
+                                5 * mem::size_of::<GLfloat>() as GLint,
+                                ptr::null());
+        gl::EnableVertexAttribArray(0);
+        // aTex = 1
+        gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE,
+                                5 * mem::size_of::<GLfloat>() as GLint,
+                                (3 * mem::size_of::<GLfloat>()) as *const GLvoid);
+        gl::EnableVertexAttribArray(1);
+
+        Billboard {
+            vao: vao
+        }
+    }
+
+    /// Binds the quad and selects the texture layer of `textype`,
+    /// unshaded like the original sprites.
+    pub unsafe fn set_up(&self,
+                         shader_program: &Shader,
+                         textures: &TextureArray,
+                         textype: TexType) {
+        gl::BindVertexArray(self.vao);
+
+        shader_program.set_bool(c_str!("billboard"), true);
+        shader_program.set_bool(c_str!("shaded"), false);
+
+        shader_program.set_int(c_str!("tex"), textures.number as i32);
+        textures.select_sprite(shader_program, textype);
+    }
+
+    /// Draws the quad, the model matrix must be set already.
+    pub unsafe fn draw(&self) {
+        gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, ptr::null());
+    }
+}
@@ -1,14 +1,26 @@
+use std::f32::consts::{PI, FRAC_PI_2};
+
 use cgmath::prelude::*;
 use cgmath::{Matrix3, Point3, vec3, Vector3, Rad};
 
+use path::Corner;
+use easing::{Easing, Tween};
+
 const MOVE_SPEED: f32 = 2.0;
 const TURN_SPEED: f32 = 2.5;
+// seconds a quarter turn and rolling over take
+const TURN_TIME: f32 = 0.6;
+const ROLL_TIME: f32 = 1.25;
+// how far to each side `glance` looks
+const GLANCE_ANGLE: f32 = 0.5;
 
 pub struct Camera {
     pub pos: Point3<f32>,
     pub dir: Vector3<f32>,
     pub up: Vector3<f32>,
     pub upside_down: bool,
+    // multiplies every movement, for speed boosts
+    pub speed: f32
 }
 
 impl Camera {
@@ -17,7 +29,8 @@ impl Camera {
             pos: Point3::new(i as f32 + 0.5, 0.0, j as f32 + 0.5),
             dir: dir,
             up: vec3(0.0, 1.0, 0.0),
-            upside_down: false
+            upside_down: false,
+            speed: 1.0
         }
     }
 
@@ -34,25 +47,22 @@ impl Camera {
         }
     }
 
-    pub fn rotate_to(&mut self, v_dir: Vector3<f32>, dt: f32) -> bool {
-        let sign = self.rotation_sign(&self.dir, &v_dir);
-
-        self.dir = Matrix3::from_axis_angle(
-            self.up, Rad(dt * sign * TURN_SPEED)) * self.dir;
-
-        // if the rotation went through the objective, just assign it
-        let new_sign = self.rotation_sign(&self.dir, &v_dir);
-        if (sign * new_sign) < 0.0 {
-            self.dir = v_dir;
-        }
+    /// A turn around `up` from where it's looking to `v_dir`,
+    /// a quarter turn taking `TURN_TIME`.
+    pub fn turn_towards(&self, v_dir: Vector3<f32>, easing: Easing) -> Tween {
+        let angle = self.rotation_sign(&self.dir, &v_dir) * self.dir.angle(v_dir).0;
+        let duration = TURN_TIME * angle.abs() / FRAC_PI_2 / self.speed;
+        Tween::new(self.dir, angle, duration, easing)
+    }
 
-        self.looking_at(v_dir)
+    pub fn turn(&mut self, tween: &Tween) {
+        self.dir = Matrix3::from_axis_angle(self.up, Rad(tween.current())) * tween.from;
     }
 
     pub fn move_to(&mut self, p_to: Point3<f32>, dt: f32) -> bool {
         let old_dir = (p_to - self.pos).normalize();
 
-        self.pos += MOVE_SPEED * dt * self.dir;
+        self.pos += MOVE_SPEED * self.speed * dt * self.dir;
 
         // if new_dir is opposite direction from old_dir
         // then we went through, just assign it
@@ -65,16 +75,36 @@ impl Camera {
         }
     }
 
-    pub fn roll_to(&mut self, v_dir: Vector3<f32>, dt: f32) -> bool {
-        self.up = Matrix3::from_axis_angle(
-            self.dir,
-            Rad(dt * TURN_SPEED)) * self.up;
+    /// Half a roll around `dir`, to be upside down or back, taking `ROLL_TIME`.
+    pub fn roll_over(&self, easing: Easing) -> Tween {
+        Tween::new(self.up, PI, ROLL_TIME / self.speed, easing)
+    }
 
-        if self.up.angle(v_dir) < Rad(0.1) {
-            self.up = v_dir;
-            true
-        } else {
-            false
-        }
+    pub fn roll(&mut self, tween: &Tween) {
+        self.up = Matrix3::from_axis_angle(self.dir, Rad(tween.current())) * tween.from;
+    }
+
+    /// Turns around `up` for at most `angle` radians,
+    /// returning how much is left to turn.
+    pub fn spin(&mut self, angle: f32, dt: f32) -> f32 {
+        let step = (dt * TURN_SPEED * self.speed).min(angle);
+        self.dir = Matrix3::from_axis_angle(self.up, Rad(step)) * self.dir;
+        angle - step
+    }
+
+    /// Looks to one side of `v_dir` and then to the other, and back
+    /// at it, as `t` goes from 0 to 1.
+    pub fn glance(&mut self, v_dir: Vector3<f32>, t: f32) {
+        let angle = (t.min(1.0) * 2.0 * PI).sin() * GLANCE_ANGLE;
+        self.dir = Matrix3::from_axis_angle(self.up, Rad(angle)) * v_dir;
+    }
+
+    /// Moves along `corner` looking where it goes, `t` being how far
+    /// along it is. Returns the new `t`, 1 at the end of the curve.
+    pub fn follow(&mut self, corner: &Corner, t: f32, dt: f32) -> f32 {
+        let t = (t + MOVE_SPEED * self.speed * dt / corner.length()).min(1.0);
+        self.pos = corner.point(t);
+        self.dir = corner.tangent(t);
+        t
     }
 }
@@ -0,0 +1,89 @@
+use std::f32::consts::PI;
+
+use cgmath::Vector3;
+
+/// How an animation progresses over its duration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    /// Constant speed, like the original.
+    Linear,
+    /// Sine shaped, slow at both ends.
+    EaseInOut,
+    /// Like `EaseInOut` but snappier in the middle.
+    Cubic,
+    /// Overshoots and wobbles into place.
+    Elastic
+}
+
+/// A rotation of `angle` radians from `from`, taking exactly `duration`
+/// seconds no matter the frame rate.
+#[derive(Debug, Clone, Copy)]
+pub struct Tween {
+    pub from: Vector3<f32>,
+    pub angle: f32,
+    elapsed: f32,
+    duration: f32,
+    easing: Easing
+}
+
+impl Easing {
+    pub fn parse(name: &str) -> Option<Easing> {
+        match name {
+            "linear" => Some(Easing::Linear),
+            "ease-in-out" => Some(Easing::EaseInOut),
+            "cubic" => Some(Easing::Cubic),
+            "elastic" => Some(Easing::Elastic),
+            _ => None
+        }
+    }
+
+    /// Maps time to progress, both from 0 to 1.
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.max(0.0).min(1.0);
+        match *self {
+            Easing::Linear => t,
+            Easing::EaseInOut => (1.0 - (PI * t).cos()) / 2.0,
+            Easing::Cubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Easing::Elastic => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    2f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * 2.0 * PI / 3.0).sin() + 1.0
+                }
+            }
+        }
+    }
+}
+
+impl Tween {
+    pub fn new(from: Vector3<f32>, angle: f32, duration: f32, easing: Easing) -> Tween {
+        Tween {
+            from: from,
+            angle: angle,
+            elapsed: 0.0,
+            duration: duration,
+            easing: easing
+        }
+    }
+
+    /// Moves the animation `dt` seconds forward.
+    pub fn advance(&mut self, dt: f32) {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+    }
+
+    /// The angle rotated so far.
+    pub fn current(&self) -> f32 {
+        let t = if self.duration > 0.0 { self.elapsed / self.duration } else { 1.0 };
+        self.angle * self.easing.apply(t)
+    }
+
+    pub fn done(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
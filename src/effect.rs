@@ -0,0 +1,95 @@
+use rand::{self, Rng};
+
+/// What happens when the camera walks into a solid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect {
+    /// Roll 180° and walk upside down, like the original.
+    Roll,
+    /// Spin a full turn in place.
+    Spin,
+    /// Turn around and walk back.
+    Reverse,
+    /// Appear in a random cell.
+    Teleport,
+    /// Move faster for a while.
+    Speed,
+    /// Switch to the next built-in theme.
+    Theme
+}
+
+/// How likely each effect is, like `roll=3,spin=1`.
+#[derive(Debug, Clone)]
+pub struct Weights {
+    weights: Vec<(Effect, u32)>
+}
+
+impl Effect {
+    pub fn parse(name: &str) -> Option<Effect> {
+        match name {
+            "roll" => Some(Effect::Roll),
+            "spin" => Some(Effect::Spin),
+            "reverse" => Some(Effect::Reverse),
+            "teleport" => Some(Effect::Teleport),
+            "speed" => Some(Effect::Speed),
+            "theme" => Some(Effect::Theme),
+            _ => None
+        }
+    }
+}
+
+impl Weights {
+    /// Comma separated `effect=weight` pairs, missing effects never happen.
+    pub fn parse(spec: &str) -> Result<Weights, String> {
+        let mut weights = Vec::new();
+
+        for pair in spec.split(',').filter(|s| !s.is_empty()) {
+            let mut kv = pair.splitn(2, '=');
+            let name = kv.next().unwrap_or("");
+            let effect = Effect::parse(name)
+                .ok_or_else(|| format!("unknown effect `{}`", name))?;
+            let weight = match kv.next() {
+                Some(w) => w.parse().map_err(|_| format!("bad weight `{}`", w))?,
+                None => 1
+            };
+            weights.push((effect, weight));
+        }
+
+        if weights.iter().all(|&(_, w)| w == 0) {
+            return Err("no effect can happen".to_string());
+        }
+
+        Ok(Weights {
+            weights: weights
+        })
+    }
+
+    pub fn choose(&self) -> Effect {
+        let total: u32 = self.weights.iter().map(|&(_, w)| w).sum();
+        let mut n = rand::thread_rng().gen_range(0, total);
+
+        for &(effect, weight) in &self.weights {
+            if n < weight {
+                return effect;
+            }
+            n -= weight;
+        }
+
+        unreachable!()
+    }
+}
+
+impl Default for Weights {
+    /// Mostly the classic roll, with a bit of everything else.
+    fn default() -> Weights {
+        Weights {
+            weights: vec![
+                (Effect::Roll, 5),
+                (Effect::Spin, 2),
+                (Effect::Reverse, 1),
+                (Effect::Teleport, 1),
+                (Effect::Speed, 1),
+                (Effect::Theme, 1)
+            ]
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::error;
+use std::fmt;
+use std::io;
+
+use image;
+
+/// Everything that can go wrong while loading the assets.
+#[derive(Debug)]
+pub enum Error {
+    /// A file couldn't be read.
+    Io {
+        path: String,
+        err: io::Error
+    },
+    /// A shader stage didn't compile, `log` is what the driver said.
+    Compile {
+        path: String,
+        stage: &'static str,
+        log: String
+    },
+    /// The compiled stages didn't link into a program.
+    Link {
+        vertex_path: String,
+        fragment_path: String,
+        log: String
+    },
+    /// An image couldn't be decoded.
+    Image {
+        path: String,
+        err: image::ImageError
+    },
+    /// A theme manifest is malformed or incomplete.
+    Theme {
+        path: String,
+        message: String
+    },
+    /// A mesh file is malformed or in an unknown format.
+    Mesh {
+        path: String,
+        message: String
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io { ref path, ref err } => {
+                write!(f, "failed to read {}: {}", path, err)
+            }
+            Error::Compile { ref path, stage, ref log } => {
+                write!(f, "failed to compile {} shader {}:\n{}", stage, path, log)
+            }
+            Error::Link { ref vertex_path, ref fragment_path, ref log } => {
+                write!(f, "failed to link {} and {}:\n{}", vertex_path, fragment_path, log)
+            }
+            Error::Image { ref path, ref err } => {
+                write!(f, "failed to load texture {}: {}", path, err)
+            }
+            Error::Theme { ref path, ref message } => {
+                write!(f, "bad theme {}: {}", path, message)
+            }
+            Error::Mesh { ref path, ref message } => {
+                write!(f, "bad mesh {}: {}", path, message)
+            }
+        }
+    }
+}
+
+impl error::Error for Error {}
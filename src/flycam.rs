@@ -0,0 +1,82 @@
+use cgmath::prelude::*;
+use cgmath::{Point3, vec3, Vector3};
+use glfw::{Action, Key, Window};
+
+use camera::Camera;
+
+const FLY_SPEED: f32 = 3.0;
+// with shift held
+const FAST_SPEED: f32 = 12.0;
+// radians per pixel the mouse moves
+const SENSITIVITY: f32 = 0.003;
+// just short of straight up or down, where yaw gets lost
+const MAX_PITCH: f32 = 1.5;
+const UP: Vector3<f32> = Vector3 { x: 0.0, y: 1.0, z: 0.0 };
+
+/// A camera detached from the walker to look at the maze from anywhere,
+/// for debugging.
+pub struct FlyCam {
+    pub pos: Point3<f32>,
+    yaw: f32,
+    pitch: f32,
+    // where the cursor was last frame, to know how much it moved
+    cursor: Option<(f64, f64)>,
+    /// Whether everything else stops while flying.
+    pub frozen: bool
+}
+
+impl FlyCam {
+    /// Starts where `camera` is, looking the same way.
+    pub fn new(camera: &Camera) -> FlyCam {
+        let dir = camera.dir.normalize();
+        FlyCam {
+            pos: camera.pos,
+            yaw: dir.z.atan2(dir.x),
+            pitch: dir.y.asin(),
+            cursor: None,
+            frozen: false
+        }
+    }
+
+    /// A camera to draw from, looking where this one does.
+    pub fn to_camera(&self) -> Camera {
+        let mut camera = Camera::new(0, 0, self.dir());
+        camera.pos = self.pos;
+        camera
+    }
+
+    fn dir(&self) -> Vector3<f32> {
+        vec3(self.pitch.cos() * self.yaw.cos(),
+             self.pitch.sin(),
+             self.pitch.cos() * self.yaw.sin())
+    }
+
+    /// The mouse looks around, WASD moves, space and control go up and
+    /// down, shift goes faster.
+    pub fn update(&mut self, window: &Window, dt: f32) {
+        let (x, y) = window.get_cursor_pos();
+        if let Some((last_x, last_y)) = self.cursor {
+            self.yaw += (x - last_x) as f32 * SENSITIVITY;
+            self.pitch -= (y - last_y) as f32 * SENSITIVITY;
+            self.pitch = self.pitch.max(-MAX_PITCH).min(MAX_PITCH);
+        }
+        self.cursor = Some((x, y));
+
+        let pressed = |key| window.get_key(key) == Action::Press;
+        let dir = self.dir();
+        let right = dir.cross(UP).normalize();
+        let mut step = vec3(0.0, 0.0, 0.0);
+
+        if pressed(Key::W) { step += dir; }
+        if pressed(Key::S) { step -= dir; }
+        if pressed(Key::A) { step -= right; }
+        if pressed(Key::D) { step += right; }
+        if pressed(Key::Space) { step += UP; }
+        if pressed(Key::LeftControl) { step -= UP; }
+
+        if step.magnitude2() > 0.0 {
+            let speed = if pressed(Key::LeftShift) { FAST_SPEED } else { FLY_SPEED };
+            self.pos += step.normalize() * speed * dt;
+        }
+    }
+}
@@ -1,92 +1,124 @@
 use std::mem;
 use std::ptr;
 use std::ffi::CStr;
+use std::collections::HashMap;
 
 use gl;
 use gl::types::*;
 use cgmath::{Matrix4, Deg, Vector3};
+use rand::{self, Rng};
 
 use shader::Shader;
-
-const VERTICES: [f32; 360] = [
-     0.      ,  0.     , -1.      ,  0.1875965, -0.5773537, -0.794651 ,
-     0.7236  , -0.52572, -0.447215,  0.1875965, -0.5773537, -0.794651 ,
-    -0.276385, -0.85064, -0.447215,  0.1875965, -0.5773537, -0.794651 ,
-     0.7236  , -0.52572, -0.447215,  0.6070647,  0.       , -0.7946525,
-     0.      ,  0.     , -1.      ,  0.6070647,  0.       , -0.7946525,
-     0.7236  ,  0.52572, -0.447215,  0.6070647,  0.       , -0.7946525,
-     0.      ,  0.     , -1.      , -0.4911221, -0.356829 , -0.7946522,
-    -0.276385, -0.85064, -0.447215, -0.4911221, -0.356829 , -0.7946522,
-    -0.894425,  0.     , -0.447215, -0.4911221, -0.356829 , -0.7946522,
-     0.      ,  0.     , -1.      , -0.4911221,  0.356829 , -0.7946522,
-    -0.894425,  0.     , -0.447215, -0.4911221,  0.356829 , -0.7946522,
-    -0.276385,  0.85064, -0.447215, -0.4911221,  0.356829 , -0.7946522,
-     0.      ,  0.     , -1.      ,  0.1875965,  0.5773537, -0.794651 ,
-    -0.276385,  0.85064, -0.447215,  0.1875965,  0.5773537, -0.794651 ,
-     0.7236  ,  0.52572, -0.447215,  0.1875965,  0.5773537, -0.794651 ,
-     0.7236  , -0.52572, -0.447215,  0.9822462,  0.       , -0.1875968,
-     0.7236  ,  0.52572, -0.447215,  0.9822462,  0.       , -0.1875968,
-     0.894425,  0.     ,  0.447215,  0.9822462,  0.       , -0.1875968,
-    -0.276385, -0.85064, -0.447215,  0.3035356, -0.9341716, -0.1875891,
-     0.7236  , -0.52572, -0.447215,  0.3035356, -0.9341716, -0.1875891,
-     0.276385, -0.85064,  0.447215,  0.3035356, -0.9341716, -0.1875891,
-    -0.894425,  0.     , -0.447215, -0.7946492, -0.5773594, -0.1875869,
-    -0.276385, -0.85064, -0.447215, -0.7946492, -0.5773594, -0.1875869,
-    -0.7236  , -0.52572,  0.447215, -0.7946492, -0.5773594, -0.1875869,
-    -0.276385,  0.85064, -0.447215, -0.7946492,  0.5773594, -0.1875869,
-    -0.894425,  0.     , -0.447215, -0.7946492,  0.5773594, -0.1875869,
-    -0.7236  ,  0.52572,  0.447215, -0.7946492,  0.5773594, -0.1875869,
-     0.7236  ,  0.52572, -0.447215,  0.3035356,  0.9341716, -0.1875891,
-    -0.276385,  0.85064, -0.447215,  0.3035356,  0.9341716, -0.1875891,
-     0.276385,  0.85064,  0.447215,  0.3035356,  0.9341716, -0.1875891,
-     0.7236  , -0.52572, -0.447215,  0.7946492, -0.5773594,  0.1875869,
-     0.894425,  0.     ,  0.447215,  0.7946492, -0.5773594,  0.1875869,
-     0.276385, -0.85064,  0.447215,  0.7946492, -0.5773594,  0.1875869,
-    -0.276385, -0.85064, -0.447215, -0.3035356, -0.9341716,  0.1875892,
-     0.276385, -0.85064,  0.447215, -0.3035356, -0.9341716,  0.1875892,
-    -0.7236  , -0.52572,  0.447215, -0.3035356, -0.9341716,  0.1875892,
-    -0.894425,  0.     , -0.447215, -0.9822462,  0.       ,  0.1875968,
-    -0.7236  , -0.52572,  0.447215, -0.9822462,  0.       ,  0.1875968,
-    -0.7236  ,  0.52572,  0.447215, -0.9822462,  0.       ,  0.1875968,
-    -0.276385,  0.85064, -0.447215, -0.3035356,  0.9341716,  0.1875892,
-    -0.7236  ,  0.52572,  0.447215, -0.3035356,  0.9341716,  0.1875892,
-     0.276385,  0.85064,  0.447215, -0.3035356,  0.9341716,  0.1875892,
-     0.7236  ,  0.52572, -0.447215,  0.7946492,  0.5773594,  0.1875869,
-     0.276385,  0.85064,  0.447215,  0.7946492,  0.5773594,  0.1875869,
-     0.894425,  0.     ,  0.447215,  0.7946492,  0.5773594,  0.1875869,
-     0.276385, -0.85064,  0.447215,  0.4911221, -0.356829 ,  0.7946522,
-     0.894425,  0.     ,  0.447215,  0.4911221, -0.356829 ,  0.7946522,
-     0.      ,  0.     ,  1.      ,  0.4911221, -0.356829 ,  0.7946522,
-    -0.7236  , -0.52572,  0.447215, -0.1875965, -0.5773537,  0.7946512,
-     0.276385, -0.85064,  0.447215, -0.1875965, -0.5773537,  0.7946512,
-     0.      ,  0.     ,  1.      , -0.1875965, -0.5773537,  0.7946512,
-    -0.7236  ,  0.52572,  0.447215, -0.6070647,  0.       ,  0.7946525,
-    -0.7236  , -0.52572,  0.447215, -0.6070647,  0.       ,  0.7946525,
-     0.      ,  0.     ,  1.      , -0.6070647,  0.       ,  0.7946525,
-     0.276385,  0.85064,  0.447215, -0.1875965,  0.5773537,  0.794651 ,
-    -0.7236  ,  0.52572,  0.447215, -0.1875965,  0.5773537,  0.794651 ,
-     0.      ,  0.     ,  1.      , -0.1875965,  0.5773537,  0.794651 ,
-     0.894425,  0.     ,  0.447215,  0.4911221,  0.356829 ,  0.7946522,
-     0.276385,  0.85064,  0.447215,  0.4911221,  0.356829 ,  0.7946522,
-     0.      ,  0.     ,  1.      ,  0.4911221,  0.356829 ,  0.7946522
+use mesh::Mesh;
+use solid::Solid;
+use effect::Effect;
+
+// colors the solids are randomly painted with
+const COLORS: [[f32; 3]; 6] = [
+    [0.8, 0.1, 0.5],
+    [0.9, 0.2, 0.1],
+    [0.9, 0.8, 0.1],
+    [0.1, 0.8, 0.2],
+    [0.1, 0.5, 0.9],
+    [0.6, 0.2, 0.9]
 ];
 
+// how sharp the highlights can be, from plastic to polished
+const SHININESS: [f32; 3] = [16.0, 64.0, 128.0];
+
+/// Phong lighting properties of a solid.
+#[derive(Debug, Clone, Copy)]
+pub struct Material {
+    pub diffuse: Vector3<f32>,
+    pub specular: Vector3<f32>,
+    pub shininess: f32,
+    // light given off even where nothing lights it
+    pub emissive: Vector3<f32>
+}
+
+/// A spinning solid, walking into it triggers its effect.
 #[derive(Debug)]
 pub struct Ico {
     pub pos: Vector3<f32>,
     pub axis: Vector3<f32>,
+    pub solid: Solid,
+    pub material: Material,
+    pub effect: Effect,
     pub active: bool
 }
 
+impl Material {
+    /// A shiny material of one of the palette colors.
+    pub fn random() -> Material {
+        let mut rng = rand::thread_rng();
+        let c = rng.choose(&COLORS).unwrap();
+        let diffuse = Vector3::new(c[0], c[1], c[2]);
+
+        Material {
+            diffuse: diffuse,
+            specular: Vector3::new(0.9, 0.9, 0.9),
+            shininess: *rng.choose(&SHININESS).unwrap(),
+            emissive: diffuse * 0.05
+        }
+    }
+
+    unsafe fn set(&self, shader_program: &Shader) {
+        shader_program.set_vec3(c_str!("material.diffuse"), self.diffuse);
+        shader_program.set_vec3(c_str!("material.specular"), self.specular);
+        shader_program.set_float(c_str!("material.shininess"), self.shininess);
+        shader_program.set_vec3(c_str!("material.emissive"), self.emissive);
+    }
+}
+
+// the VAO of one kind of solid
+#[derive(Debug)]
+struct Shape {
+    vao: GLuint,
+    vertex_count: GLsizei
+}
+
 #[derive(Debug)]
 pub struct IcoRenderer {
-    vao: GLuint
+    shapes: HashMap<Solid, Shape>
 }
 
 impl IcoRenderer {
 
-    pub unsafe fn new() -> IcoRenderer {
+    pub unsafe fn new(meshes: &[(Solid, Mesh)]) -> IcoRenderer {
+        let shapes = meshes.iter()
+            .map(|&(solid, ref mesh)| (solid, Shape::new(mesh)))
+            .collect();
+
+        IcoRenderer {
+            shapes: shapes
+        }
+    }
+
+    pub unsafe fn set_up(&self, shader_program: &Shader) {
+        shader_program.set_bool(c_str!("billboard"), false);
+        shader_program.set_bool(c_str!("shaded"), true);
+    }
+
+    pub unsafe fn draw(&self, shader_program: &Shader, ico: &Ico, t: f32) {
+        let shape = &self.shapes[&ico.solid];
+        gl::BindVertexArray(shape.vao);
+
+        let model = Matrix4::from_translation(ico.pos) *
+                    Matrix4::from_axis_angle(ico.axis, Deg(t * 100.0)) *
+                    Matrix4::from_scale(0.25);
+        shader_program.set_mat4(c_str!("model"), model);
+        ico.material.set(shader_program);
+
+        gl::DrawArrays(gl::TRIANGLES, 0, shape.vertex_count);
+    }
+}
+
+impl Shape {
+
+    unsafe fn new(mesh: &Mesh) -> Shape {
         let (mut vao, mut vbo) = (0, 0);
+        let vertices = mesh.interleaved();
+        let stride = 8 * mem::size_of::<GLfloat>() as GLint;
 
         //* generate VAO, VBO, EBO
         gl::GenVertexArrays(1, &mut vao);
@@ -98,41 +130,26 @@ impl IcoRenderer {
         //* VBO data
         gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
         gl::BufferData(gl::ARRAY_BUFFER,
-                       mem::size_of::<[f32; 360]>() as isize,
-                       VERTICES.as_ptr() as *const _,
+                       (vertices.len() * mem::size_of::<GLfloat>()) as isize,
+                       vertices.as_ptr() as *const _,
                        gl::STATIC_DRAW);
 
         //* vertex attribs
         // aPos = 0
-        gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE,
-                                6 * mem::size_of::<GLfloat>() as GLint,
-                                ptr::null());
+        gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, ptr::null());
         gl::EnableVertexAttribArray(0);
-        // aNor = 2
-        gl::VertexAttribPointer(2, 3, gl::FLOAT, gl::FALSE,
-                                6 * mem::size_of::<GLfloat>() as GLint,
+        // aTex = 1
+        gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, stride,
                                 (3 * mem::size_of::<GLfloat>()) as *const GLvoid);
+        gl::EnableVertexAttribArray(1);
+        // aNor = 2
+        gl::VertexAttribPointer(2, 3, gl::FLOAT, gl::FALSE, stride,
+                                (5 * mem::size_of::<GLfloat>()) as *const GLvoid);
         gl::EnableVertexAttribArray(2);
 
-        IcoRenderer {
-            vao: vao
+        Shape {
+            vao: vao,
+            vertex_count: mesh.vertex_count() as GLsizei
         }
     }
-
-    pub unsafe fn set_up(&self, shader_program: &Shader) {
-        gl::BindVertexArray(self.vao);
-
-        shader_program.set_bool(c_str!("rat"), false);
-        shader_program.set_bool(c_str!("shaded"), true);
-    }
-
-    pub unsafe fn draw(&self, shader_program: &Shader, ico: &Ico, t: f32) {
-
-        let model = Matrix4::from_translation(ico.pos) *
-                    Matrix4::from_axis_angle(ico.axis, Deg(t * 100.0)) *
-                    Matrix4::from_scale(0.25);
-        shader_program.set_mat4(c_str!("model"), model);
-
-        gl::DrawArrays(gl::TRIANGLES, 0, 60);
-    }
 }
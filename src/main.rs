@@ -5,6 +5,7 @@ extern crate cgmath;
 extern crate rand;
 
 mod util;
+mod error;
 mod ico;
 mod rat;
 mod shader;
@@ -13,37 +14,123 @@ mod wall;
 mod walker;
 mod camera;
 mod texture;
+mod options;
+mod postprocess;
+mod assets;
+mod theme;
+mod procedural;
+mod mesh;
+mod solid;
+mod effect;
+mod billboard;
+mod smiley;
+mod behaviour;
+mod spawner;
+mod path;
+mod easing;
+mod player;
+mod flycam;
+mod minimap;
 
-use std::env;
 use std::cmp;
+use std::mem;
+use std::f32;
+use std::process;
 use std::ffi::CStr;
 use std::collections::HashMap;
 
-use cgmath::{Matrix3, Matrix4, Deg, perspective, vec3, InnerSpace};
+use cgmath::{Matrix4, Vector3, Deg, perspective, vec3, InnerSpace, EuclideanSpace};
 use glfw::{Action, Context, Key};
+use rand::Rng;
 
 use wall::{Wall, WallRenderer};
-use ico::{Ico, IcoRenderer};
-use rat::{Rat, RatRenderer};
+use ico::{Ico, IcoRenderer, Material};
+use rat::{Rat, RatRenderer, Reaction};
+use smiley::{Smiley, SmileyRenderer};
+use behaviour::{Behaviour, Surroundings};
+use spawner::{Spawner, Rates};
+use path::Corner;
+use easing::{Easing, Tween};
+use player::Player;
+use flycam::FlyCam;
+use minimap::Minimap;
 use shader::Shader;
 use maze::Maze;
-use walker::Walker;
+use walker::{Walker, Direction};
 use camera::Camera;
-use texture::{Texture, TexType};
+use texture::TexType;
+use options::Options;
+use error::Error;
+use assets::Assets;
+use theme::Theme;
+use postprocess::PostProcessor;
+use mesh::Mesh;
+use solid::Solid;
+use effect::{Effect, Weights};
 
 
 const WIDTH: u32 = 800;
 const HEIGHT: u32 = 600;
+// seconds between checks for modified shaders
+const RELOAD_INTERVAL: f64 = 0.5;
+// speed effect: how much faster and for how many seconds
+const BOOST_SPEED: f32 = 2.0;
+const BOOST_TIME: f64 = 5.0;
+// rat reactions: longest pause and how long looking around takes
+const MAX_WAIT: f32 = 3.0;
+const LOOK_TIME: f32 = 1.0;
 
 
 #[derive(Debug)]
 enum State {
     Walking,
-    Turning,
-    Rolling
+    Turning(Tween),
+    // looking for a solid in the next cell
+    Checking,
+    Rolling(Tween),
+    // radians left of a full turn
+    Spinning(f32),
+    // seconds left to wait for a rat to leave
+    Waiting(f32),
+    // seconds since it started looking around
+    Looking(f32),
+    // following a rounded corner, how far along it
+    Cornering(Corner, f32),
+    // with --play, the keys move the camera instead
+    Playing
+}
+
+// billboards, drawn in the transparent pass
+enum Sprite<'a, 'b: 'a> {
+    Rat(&'a Rat<'b>),
+    Smiley(&'a Smiley)
+}
+
+impl<'a, 'b> Sprite<'a, 'b> {
+    fn pos(&self) -> Vector3<f32> {
+        match *self {
+            Sprite::Rat(rat) => rat.pos,
+            Sprite::Smiley(smiley) => smiley.pos
+        }
+    }
 }
 
 fn main() {
+    let options = Options::from_args();
+
+    if let Err(err) = run(&options) {
+        eprintln!("win95-maze: {}", err);
+        process::exit(1);
+    }
+}
+
+fn run(options: &Options) -> Result<(), Error> {
+    let assets = Assets::new(options.assets.clone());
+    // embedded shaders never change, say so when developing
+    if cfg!(debug_assertions) && options.assets.is_none() {
+        println!("Shaders are embedded, run with --assets . to reload them when edited.");
+    }
+
     let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
 
     glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
@@ -55,8 +142,7 @@ fn main() {
 
     let (mut window, events) = glfw.with_primary_monitor(
         |glfw: &mut _, m: Option<&glfw::Monitor>| {
-            let fullscreen = env::args().any(|a| a == "--fullscreen");
-            let (mode, w, h) = if fullscreen {
+            let (mode, w, h) = if options.fullscreen {
                 let vid = m.unwrap().get_video_mode().unwrap();
                 (glfw::WindowMode::FullScreen(m.unwrap()),
                  vid.width,
@@ -70,6 +156,7 @@ fn main() {
         });
 
     let (width, height) = window.get_size();
+    let (fb_width, fb_height) = window.get_framebuffer_size();
     window.set_key_polling(true);
     window.make_current();
 
@@ -78,142 +165,427 @@ fn main() {
     // vsync off
     //glfw.set_swap_interval(glfw::SwapInterval::None);
 
-    let maze = Maze::new(20, 20);
-    maze.print();
-
-    let mut state = State::Walking;
-
     let ratio = width as f32 / height as f32;
 
-    let (shader_program, textures) = unsafe {
-        let proj = perspective(Deg(60.0), ratio, 0.1, 100.0);
-        (set_up_shaders(proj), set_up_textures())
+    let proj = perspective(Deg(60.0), ratio, 0.1, 100.0);
+    let theme = Theme::load(&assets, &options.theme)?;
+    // custom themes are followed by the first built-in one
+    let mut theme_index = theme::BUILTIN.iter()
+        .position(|&name| name == options.theme)
+        .unwrap_or(theme::BUILTIN.len() - 1);
+    let (mut shader_program, mut textures) = unsafe {
+        (set_up_shaders(&assets, proj)?, theme.load_textures(&assets, options.filter)?)
     };
 
-    let mut wall_renderer = unsafe { WallRenderer::new() };
-    let walls = gen_walls(&maze);
+    let wall_renderer = unsafe { WallRenderer::new() };
 
-    let ico_renderer = unsafe { IcoRenderer::new() };
-    let mut icos = gen_icos(&maze);
+    let meshes = gen_meshes(&assets, &options.mesh)?;
+    let ico_renderer = unsafe { IcoRenderer::new(&meshes) };
 
     let rat_renderer = unsafe { RatRenderer::new() };
-    let mut rats = gen_rats(&maze);
+    let smiley_renderer = unsafe { SmileyRenderer::new() };
 
-    let mut walker = Walker::new(&maze, 0, 0);
-    let mut camera = Camera::new(0, 0, walker.direction.to_vec());
-    walker.next();
+    let mut post_processor = unsafe {
+        PostProcessor::new(&assets, &options.post, fb_width, fb_height)?
+    };
+    // M cycles through hidden, in a corner and full screen
+    let mut minimap = unsafe { Minimap::new(&assets)? };
 
     let mut frame_count = 0;
     let mut last_second = glfw.get_time();
     let mut last_frame = glfw.get_time();
+    // stops while the free-fly camera freezes everything
+    let mut sim_time = glfw.get_time();
+    // F1 detaches it from the walker, F2 freezes the rest
+    let mut fly: Option<FlyCam> = None;
+    let mut last_reload = glfw.get_time();
+
+    // a new maze every time the smiley is found
+    'mazes: loop {
+        let maze = Maze::new(20, 20);
+        maze.print();
+
+        let mut state = if options.play.is_some() { State::Playing } else { State::Walking };
+
+        let walls = gen_walls(&maze);
+        unsafe { minimap.set_maze(&maze) };
+        let decals = gen_decals(&maze);
+        let mut icos = gen_icos(&maze, &options.spawn, &options.effects);
+        let mut rats = gen_rats(&maze, &options.spawn);
+        let mut spawner = Spawner::new(options.spawn.clone());
+        // when playing, the smiley waits at the exit in the far corner
+        let smiley = match options.play {
+            Some(_) => Smiley::new(maze.height - 1, maze.width - 1),
+            None => gen_smiley(&maze)
+        };
 
-    while !window.should_close() {
-        // input and stuff
-        for (_, event) in glfw::flush_messages(&events) {
-            handle_window_event(&mut window, event);
-        }
-
-        let current_time = glfw.get_time();
-        let delta_time = (current_time - last_frame) as f32;
-        last_frame = current_time;
+        let mut walker = Walker::new(&maze, 0, 0);
+        let mut camera = Camera::new(0, 0, walker.direction.to_vec());
+        let mut player = options.play.map(|movement| Player::new(movement, walker.clone()));
+        walker.next();
 
-        // camera movement
-        let completed = match state {
-            State::Walking => {
-                camera.move_to(walker.to_point(), delta_time)
-            }
-            State::Turning => {
-                let v_dir = walker.direction.to_vec();
-                camera.rotate_to(v_dir, delta_time)
+        let mut boost_end = 0.0;
+        // the cell the camera already reacted to a rat in
+        let mut reacted_at = None;
+        // with --smooth, the step after the current one is decided
+        // in advance to know if there's a corner ahead
+        let mut upcoming: Option<Walker> = None;
+
+        while !window.should_close() {
+            // input and stuff
+            for (_, event) in glfw::flush_messages(&events) {
+                match event {
+                    glfw::WindowEvent::Key(Key::F1, _, Action::Press, _) => {
+                        // back to where the walker is, which kept going unless frozen
+                        fly = match fly {
+                            Some(_) => {
+                                window.set_cursor_mode(glfw::CursorMode::Normal);
+                                None
+                            }
+                            None => {
+                                window.set_cursor_mode(glfw::CursorMode::Disabled);
+                                Some(FlyCam::new(&camera))
+                            }
+                        };
+                    }
+                    glfw::WindowEvent::Key(Key::F2, _, Action::Press, _) => {
+                        if let Some(ref mut fly) = fly {
+                            fly.frozen = !fly.frozen;
+                        }
+                    }
+                    glfw::WindowEvent::Key(Key::M, _, Action::Press, _) => {
+                        minimap.view = minimap.view.next();
+                    }
+                    _ => handle_window_event(&mut window, event)
+                }
             }
-            State::Rolling => {
-                let y = if camera.upside_down { 1.0 } else { -1.0 };
-                camera.roll_to(vec3(0.0, y, 0.0), delta_time)
+
+            let current_time = glfw.get_time();
+            let frame_time = (current_time - last_frame) as f32;
+            last_frame = current_time;
+
+            if let Some(ref mut fly) = fly {
+                fly.update(&window, frame_time);
             }
-        };
+            let frozen = fly.as_ref().map_or(false, |fly| fly.frozen);
+            let delta_time = if frozen { 0.0 } else { frame_time };
+            sim_time += delta_time as f64;
 
-        // next state
-        if completed {
-            state = match state {
+            // camera movement
+            let completed = match state {
+                _ if frozen => false,
                 State::Walking => {
-                    walker.next();
-                    let v_dir = walker.direction.to_vec();
-                    if camera.looking_at(v_dir) {
-                        if icos.contains_key(&walker.pos()) {
-                            State::Rolling
+                    if options.smooth.is_some() && upcoming.is_none() {
+                        let mut next = walker.clone();
+                        next.next();
+                        upcoming = Some(next);
+                    }
+                    // stop where the curve starts if there is one
+                    match corner_ahead(&walker, &upcoming, options.smooth) {
+                        Some(corner) => camera.move_to(corner.start, delta_time),
+                        None => camera.move_to(walker.to_point(), delta_time)
+                    }
+                }
+                State::Turning(ref mut tween) => {
+                    tween.advance(delta_time);
+                    camera.turn(tween);
+                    tween.done()
+                }
+                State::Checking => true,
+                State::Rolling(ref mut tween) => {
+                    tween.advance(delta_time);
+                    camera.roll(tween);
+                    tween.done()
+                }
+                State::Spinning(ref mut left) => {
+                    *left = camera.spin(*left, delta_time);
+                    *left <= 0.0
+                }
+                State::Waiting(ref mut left) => {
+                    *left -= delta_time;
+                    *left <= 0.0 || !rat_in(&rats, walker.pos())
+                }
+                State::Looking(ref mut elapsed) => {
+                    *elapsed += delta_time;
+                    camera.glance(walker.direction.to_vec(), *elapsed / LOOK_TIME);
+                    *elapsed >= LOOK_TIME
+                }
+                State::Cornering(ref corner, ref mut t) => {
+                    *t = camera.follow(corner, *t, delta_time);
+                    *t >= 1.0
+                }
+                // the keys drive the free-fly camera while it's on
+                State::Playing if fly.is_some() => false,
+                State::Playing => {
+                    if let Some(ref mut player) = player {
+                        player.update(&window, &mut camera, &maze, options.easing, delta_time);
+                    }
+                    (camera.pos.z as usize, camera.pos.x as usize) == smiley.pos()
+                }
+            };
+
+            // next state
+            if completed {
+                state = match state {
+                    State::Walking => {
+                        match corner_ahead(&walker, &upcoming, options.smooth) {
+                            Some(corner) => State::Cornering(corner, 0.0),
+                            None => {
+                                reacted_at = None;
+                                match upcoming.take() {
+                                    Some(next) => walker = next,
+                                    None => walker.next()
+                                }
+                                turn_or_check(&camera, &walker, options.easing)
+                            }
+                        }
+                    }
+                    State::Playing => {
+                        println!("Found the exit!");
+                        continue 'mazes;
+                    }
+                    State::Cornering(..) => {
+                        // already facing the next cell
+                        reacted_at = None;
+                        walker = upcoming.take().unwrap();
+                        camera.dir = walker.direction.to_vec();
+                        State::Checking
+                    }
+                    State::Turning(_) => {
+                        camera.dir = walker.direction.to_vec();
+                        State::Checking
+                    }
+                    State::Checking => {
+                        if walker.pos() == smiley.pos() {
+                            continue 'mazes;
+                        }
+
+                        let rat_ahead = options.rat_reaction != Reaction::Pass &&
+                                        reacted_at != Some(walker.pos()) &&
+                                        rat_in(&rats, walker.pos());
+                        if rat_ahead {
+                            reacted_at = Some(walker.pos());
+                            match options.rat_reaction {
+                                Reaction::Pause => State::Waiting(MAX_WAIT),
+                                Reaction::Turn => {
+                                    turn_back(&mut walker, &mut upcoming, &camera, options.easing)
+                                }
+                                Reaction::Look => State::Looking(0.0),
+                                Reaction::Pass => unreachable!()
+                            }
                         } else {
-                            State::Walking
+                            match icos.remove(&walker.pos()) {
+                                Some(ico) => match ico.effect {
+                                    Effect::Roll => State::Rolling(camera.roll_over(options.easing)),
+                                    Effect::Spin => State::Spinning(2.0 * f32::consts::PI),
+                                    Effect::Reverse => {
+                                        turn_back(&mut walker, &mut upcoming, &camera, options.easing)
+                                    }
+                                    Effect::Teleport => {
+                                        let i = rand::random::<usize>() % maze.height;
+                                        let j = rand::random::<usize>() % maze.width;
+                                        walker = Walker::new(&maze, i, j);
+                                        camera.pos = walker.to_point();
+                                        walker.next();
+                                        camera.dir = walker.direction.to_vec();
+                                        State::Checking
+                                    }
+                                    Effect::Speed => {
+                                        camera.speed = BOOST_SPEED;
+                                        boost_end = sim_time + BOOST_TIME;
+                                        State::Walking
+                                    }
+                                    Effect::Theme => {
+                                        theme_index = (theme_index + 1) % theme::BUILTIN.len();
+                                        let name = theme::BUILTIN[theme_index];
+                                        let loaded = Theme::load(&assets, name).and_then(|theme| unsafe {
+                                            theme.load_textures(&assets, options.filter)
+                                        });
+                                        match loaded {
+                                            Ok(loaded) => textures = loaded,
+                                            Err(err) => println!("{}, keeping the current theme.", err)
+                                        }
+                                        State::Walking
+                                    }
+                                },
+                                None => State::Walking
+                            }
                         }
-                    } else {
-                        State::Turning
                     }
-                }
-                State::Turning => {
-                    if icos.contains_key(&walker.pos()) {
-                        State::Rolling
-                    } else {
+                    State::Rolling(_) => {
+                        camera.upside_down = !camera.upside_down;
+                        let y = if camera.upside_down { -1.0 } else { 1.0 };
+                        camera.up = vec3(0.0, y, 0.0);
                         State::Walking
                     }
-                }
-                State::Rolling => {
-                    camera.upside_down = !camera.upside_down;
-                    icos.remove(&walker.pos());
-                    State::Walking
-                }
+                    State::Spinning(_) => State::Walking,
+                    // check again, now it's the turn of the solids
+                    State::Waiting(_) => State::Checking,
+                    State::Looking(_) => {
+                        camera.dir = walker.direction.to_vec();
+                        State::Checking
+                    }
+                };
             };
-        };
 
-        // update rats
-        for rat in &mut rats {
-            rat.update(delta_time);
-        }
+            if camera.speed != 1.0 && sim_time > boost_end {
+                camera.speed = 1.0;
+            }
 
-        // manual movement
-        //handle_input(&window, &mut camera, delta_time * 3.0);
+            // pick up shader edits
+            if current_time - last_reload > RELOAD_INTERVAL {
+                last_reload = current_time;
+                unsafe {
+                    if shader_program.reload_if_changed() {
+                        set_up_uniforms(&shader_program, proj);
+                    }
+                    post_processor.reload_if_changed();
+                    minimap.reload_if_changed();
+                }
+            }
 
-        let view = Matrix4::look_at(camera.pos,
-                                    camera.pos + camera.dir,
-                                    camera.up);
+            // update rats, which react to the camera and each other
+            let (ci, cj) = (camera.pos.z as usize, camera.pos.x as usize);
+            let distances = maze.distances(cmp::min(ci, maze.height - 1),
+                                           cmp::min(cj, maze.width - 1));
+            let mut occupied: Vec<(usize, usize)> = rats.iter().map(|r| r.walker.pos()).collect();
+            for (n, rat) in rats.iter_mut().enumerate() {
+                rat.update(delta_time, &Surroundings {
+                    distances: &distances,
+                    rats: &occupied
+                });
+                // so the next rats don't pick the cell this one just did
+                occupied[n] = rat.walker.pos();
+            }
 
-        // FPS counting
-        if cfg!(debug_assertions) && (current_time - last_second) > 1.0 {
-            last_second = current_time;
-            println!("FPS: {}", frame_count);
-            frame_count = 0;
-        } else {
-            frame_count += 1;
-        }
+            // bring back what was hit or wandered off, out of sight
+            if spawner.due(sim_time) {
+                let cell = (cmp::min(ci, maze.height - 1), cmp::min(cj, maze.width - 1));
+                let facing = Direction::nearest(camera.dir);
 
-        // rendering
-        unsafe {
-            gl::ClearColor(0.2, 0.3, 0.3, 1.0);
-            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+                if icos.len() < Rates::count(spawner.rates.icos, &maze) {
+                    let taken = |c| icos.contains_key(&c) || c == smiley.pos() || c == walker.pos();
+                    if let Some((i, j)) = spawner.hidden_cell(&maze, cell, facing, taken) {
+                        icos.insert((i, j), new_ico(i, j, &options.effects));
+                    }
+                }
 
-            // set the camera matrix
-            shader_program.set_mat4(c_str!("view"), view);
+                if let Some(max) = spawner.rates.despawn {
+                    rats.retain(|rat| {
+                        let (i, j) = rat.cell();
+                        distances[i][j] <= max
+                    });
+                }
 
-            // walls are not shaded nor rats
-            wall_renderer.set_up(&shader_program);
-            for wall in &walls {
-                wall_renderer.draw(&shader_program, &textures, wall);
+                if rats.len() < Rates::count(spawner.rates.rats, &maze) {
+                    // not right next to the camera, they'd just flee,
+                    // nor where they would be despawned right away
+                    let despawn = spawner.rates.despawn;
+                    let taken = |(i, j): (usize, usize)| {
+                        distances[i][j] <= 3 || despawn.map_or(false, |max| distances[i][j] > max)
+                    };
+                    if let Some((i, j)) = spawner.hidden_cell(&maze, cell, facing, taken) {
+                        rats.push(new_rat(&maze, i, j));
+                    }
+                }
             }
 
-            // rats have a single texture with alpha
-            rat_renderer.set_up(&shader_program, &textures);
-            for rat in &rats {
-                rat_renderer.draw(&shader_program, rat);
+            // what everything is drawn from, the free-fly camera if on
+            let flying = fly.as_ref().map(FlyCam::to_camera);
+            let viewer = flying.as_ref().unwrap_or(&camera);
+
+            let view = Matrix4::look_at(viewer.pos,
+                                        viewer.pos + viewer.dir,
+                                        viewer.up);
+
+            // FPS counting
+            if cfg!(debug_assertions) && (current_time - last_second) > 1.0 {
+                last_second = current_time;
+                println!("FPS: {}", frame_count);
+                frame_count = 0;
+            } else {
+                frame_count += 1;
             }
 
-            // finally, icos are shaded
-            ico_renderer.set_up(&shader_program);
-            for (_, ico) in &icos {
-                ico_renderer.draw(&shader_program, ico, current_time as f32);
+            // rendering
+            unsafe {
+                // draw the scene offscreen if there are effects
+                post_processor.begin();
+
+                gl::ClearColor(0.2, 0.3, 0.3, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+                // effect passes use their own programs
+                shader_program.use_program();
+
+                // set the camera matrix, and where highlights are seen from
+                shader_program.set_mat4(c_str!("view"), view);
+                shader_program.set_vec3(c_str!("viewPos"), viewer.pos.to_vec());
+
+                // opaque pass: walls are not shaded, solids are
+                wall_renderer.set_up(&shader_program, &textures);
+                for wall in &walls {
+                    wall_renderer.draw(&shader_program, &textures, wall);
+                }
+                // logos are just walls in front of the walls
+                for decal in &decals {
+                    wall_renderer.draw(&shader_program, &textures, decal);
+                }
+
+                ico_renderer.set_up(&shader_program);
+                for (_, ico) in &icos {
+                    ico_renderer.draw(&shader_program, ico, sim_time as f32);
+                }
+
+                // transparent pass: billboards blended back to front,
+                // tested against the depth buffer but not writing to it
+                gl::Enable(gl::BLEND);
+                gl::DepthMask(gl::FALSE);
+
+                let mut sprites: Vec<Sprite> = rats.iter().map(Sprite::Rat).collect();
+                sprites.push(Sprite::Smiley(&smiley));
+                sort_back_to_front(&mut sprites, viewer);
+
+                let mut last_kind = None;
+                for sprite in &sprites {
+                    // only set up again when switching renderers
+                    let kind = mem::discriminant(sprite);
+                    if last_kind != Some(kind) {
+                        match *sprite {
+                            Sprite::Rat(_) => rat_renderer.set_up(&shader_program, &textures),
+                            Sprite::Smiley(_) => smiley_renderer.set_up(&shader_program, &textures)
+                        }
+                        last_kind = Some(kind);
+                    }
+
+                    match *sprite {
+                        Sprite::Rat(rat) => {
+                            rat_renderer.draw(&shader_program, &textures, rat, viewer)
+                        }
+                        Sprite::Smiley(smiley) => {
+                            smiley_renderer.draw(&shader_program, smiley, sim_time as f32)
+                        }
+                    }
+                }
+
+                gl::DepthMask(gl::TRUE);
+                gl::Disable(gl::BLEND);
+
+                post_processor.finish(current_time as f32);
+
+                // on top of the effects, so it stays readable
+                minimap.draw(ratio, &maze, &icos, &rats, &smiley, &camera);
             }
+
+            window.swap_buffers();
+            glfw.poll_events();
         }
 
-        window.swap_buffers();
-        glfw.poll_events();
+
+        break;
     }
+
+    Ok(())
 }
 
 fn gen_walls(maze: &Maze)  -> Vec<Wall> {
@@ -222,9 +594,9 @@ fn gen_walls(maze: &Maze)  -> Vec<Wall> {
 
     fn get_rand_tex() -> TexType {
         if rand::random::<f32>() < 0.9 {
-            TexType::Brick
+            TexType::Wall
         } else {
-            TexType::Thing
+            TexType::AltWall
         }
     }
 
@@ -301,18 +673,125 @@ fn gen_walls(maze: &Maze)  -> Vec<Wall> {
         }
     }
 
-    // sort walls by textype to avoid changing uniforms so much
-    walls.sort_unstable_by_key(|w| w.textype);
     walls
 }
 
-fn gen_icos(maze: &Maze) -> HashMap<(usize, usize), Ico> {
-    // let's say there is 6% of tiles with an icosahedron
+// every solid, or the same custom mesh for all of them
+fn gen_meshes(assets: &Assets, custom: &Option<String>) -> Result<Vec<(Solid, Mesh)>, Error> {
+    let custom = match *custom {
+        Some(ref name) => Some(Mesh::load(assets, name)?),
+        None => None
+    };
+
+    Ok(Solid::ALL.iter()
+        .map(|&solid| (solid, custom.clone().unwrap_or_else(|| solid.mesh())))
+        .collect())
+}
+
+// OpenGL logos on some walls, facing into random cells
+fn gen_decals(maze: &Maze) -> Vec<Wall> {
+    // let's say 3% of the cells have a logo
     let total = maze.width * maze.height;
-    let count = cmp::max(6 * total / 100, 2);
+    let count = cmp::max(3 * total / 100, 2);
+    let indices = rand::seq::sample_indices(
+        &mut rand::thread_rng(), total, count);
+
+    let mut decals = Vec::new();
+
+    for e in indices {
+        let i = e / maze.width;
+        let j = e % maze.width;
+
+        let sides = [
+            (Direction::North, maze.north(i, j), 180.0),
+            (Direction::East, maze.east(i, j), 90.0),
+            (Direction::South, maze.south(i, j), 0.0),
+            (Direction::West, maze.west(i, j), 270.0)
+        ];
+        let walled: Vec<_> = sides.iter().filter(|side| side.1).collect();
+
+        // the back of the quad faces the cell, so the text isn't mirrored,
+        // and it's a bit off the wall so they don't fight for the depth
+        if let Some(&&(direction, _, angle_y)) = rand::thread_rng().choose(&walled) {
+            let center = vec3(j as f32 + 0.5, 0.0, i as f32 + 0.5);
+            decals.push(
+                Wall {
+                    pos: center + direction.to_vec() * 0.49,
+                    angle_y: angle_y,
+                    angle_x: 0.0,
+                    textype: TexType::Logo
+                });
+        }
+    }
+
+    decals
+}
+
+// farthest first, so nearer sprites blend over them
+fn sort_back_to_front(sprites: &mut Vec<Sprite>, camera: &Camera) {
+    let eye = camera.pos.to_vec();
+    sprites.sort_by(|a, b| {
+        let (da, db) = ((a.pos() - eye).magnitude2(), (b.pos() - eye).magnitude2());
+        db.partial_cmp(&da).unwrap_or(cmp::Ordering::Equal)
+    });
+}
+
+fn rat_in(rats: &[Rat], cell: (usize, usize)) -> bool {
+    rats.iter().any(|rat| rat.cell() == cell)
+}
+
+// the rounded corner at the walker's cell, if it turns
+// left or right there, and turning back is done in place
+fn corner_ahead(walker: &Walker, upcoming: &Option<Walker>, radius: Option<f32>) -> Option<Corner> {
+    match (upcoming, radius) {
+        (&Some(ref next), Some(radius)) => {
+            let (from, to) = (walker.direction, next.direction);
+            if to == from || to == from.opposite() {
+                None
+            } else {
+                Some(Corner::new(walker.to_point(), from.to_vec(), to.to_vec(), radius))
+            }
+        }
+        _ => None
+    }
+}
+
+// heads back the way the camera came. Right after a corner the camera is
+// past the center of the corner cell, off the line back, so it returns to
+// that center first and then curves back from there
+fn turn_back<'a>(walker: &mut Walker<'a>,
+                 upcoming: &mut Option<Walker<'a>>,
+                 camera: &Camera,
+                 easing: Easing) -> State {
+    let mut previous = walker.clone();
+    previous.reverse();
+
+    if (previous.to_point() - camera.pos).magnitude() > 0.01 {
+        let mut back = walker.clone();
+        back.go_back();
+        *walker = previous;
+        *upcoming = Some(back);
+    } else {
+        walker.go_back();
+    }
+    turn_or_check(camera, walker, easing)
+}
+
+// turn first if the walker changed direction
+fn turn_or_check(camera: &Camera, walker: &Walker, easing: Easing) -> State {
+    let v_dir = walker.direction.to_vec();
+    if camera.looking_at(v_dir) {
+        State::Checking
+    } else {
+        State::Turning(camera.turn_towards(v_dir, easing))
+    }
+}
+
+fn gen_icos(maze: &Maze, rates: &Rates, effects: &Weights) -> HashMap<(usize, usize), Ico> {
+    let total = maze.width * maze.height;
+    let count = Rates::count(rates.icos, maze);
     let indices = rand::seq::sample_indices(
         &mut rand::thread_rng(), total, count);
-    let rnd_f = || rand::random::<f32>() * 2.0 - 1.0;
 
     let mut icos = HashMap::new();
 
@@ -320,22 +799,36 @@ fn gen_icos(maze: &Maze) -> HashMap<(usize, usize), Ico> {
         let i = e / maze.width;
         let j = e % maze.width;
 
-        icos.insert(
-            (i, j),
-            Ico {
-                pos: vec3(j as f32 + 0.5, 0.0, i as f32 + 0.5),
-                axis: vec3(rnd_f(), rnd_f(), rnd_f()).normalize(),
-                active: true
-            });
+        icos.insert((i, j), new_ico(i, j, effects));
     }
 
     icos
 }
 
-fn gen_rats(maze: &Maze) -> Vec<Rat> {
-    // let's say there is 5% of tiles with a rat initially
+fn new_ico(i: usize, j: usize, effects: &Weights) -> Ico {
+    let rnd_f = || rand::random::<f32>() * 2.0 - 1.0;
+
+    Ico {
+        pos: vec3(j as f32 + 0.5, 0.0, i as f32 + 0.5),
+        axis: vec3(rnd_f(), rnd_f(), rnd_f()).normalize(),
+        solid: Solid::random(),
+        material: Material::random(),
+        effect: effects.choose(),
+        active: true
+    }
+}
+
+// anywhere but the starting cell
+fn gen_smiley(maze: &Maze) -> Smiley {
     let total = maze.width * maze.height;
-    let count = cmp::max(5 * total / 100, 2);
+    let e = rand::thread_rng().gen_range(1, total);
+
+    Smiley::new(e / maze.width, e % maze.width)
+}
+
+fn gen_rats<'a>(maze: &'a Maze, rates: &Rates) -> Vec<Rat<'a>> {
+    let total = maze.width * maze.height;
+    let count = Rates::count(rates.rats, maze);
     let indices = rand::seq::sample_indices(
         &mut rand::thread_rng(), total, count);
 
@@ -345,45 +838,45 @@ fn gen_rats(maze: &Maze) -> Vec<Rat> {
         let i = e / maze.width;
         let j = e % maze.width;
 
-        let mut walker = Walker::new(&maze, i, j);
-        walker.next();
-        vec.push(Rat {
-            pos: vec3(j as f32 + 0.5, 0.0, i as f32 + 0.5),
-            walker: walker
-        });
+        vec.push(new_rat(maze, i, j));
     }
     vec
 }
 
-unsafe fn set_up_textures() -> HashMap<TexType, Texture> {
-    let mut textures = HashMap::new();
-    textures.insert(TexType::Brick, Texture::new("resources/brick.bmp", 0));
-    textures.insert(TexType::Thing, Texture::new("resources/thing.bmp", 1));
-    textures.insert(TexType::Ceiling, Texture::new("resources/ceiling.bmp", 2));
-    textures.insert(TexType::Floor, Texture::new("resources/floor.bmp", 3));
-    textures.insert(TexType::Rat, Texture::new("resources/rat.bmp", 4));
+fn new_rat(maze: &Maze, i: usize, j: usize) -> Rat {
+    let mut walker = Walker::new(&maze, i, j);
+    walker.next();
 
-    for (_, texture) in &textures {
-        texture.bind();
+    Rat {
+        pos: vec3(j as f32 + 0.5, 0.0, i as f32 + 0.5),
+        walker: walker,
+        distance: 0.0,
+        behaviour: Behaviour::Wander
     }
-
-    textures
 }
 
-unsafe fn set_up_shaders(proj: Matrix4<f32>) -> Shader {
+unsafe fn set_up_shaders(assets: &Assets, proj: Matrix4<f32>) -> Result<Shader, Error> {
     gl::Enable(gl::DEPTH_TEST);
 
+    // sprites may have soft edges, blending is only
+    // enabled for them in the transparent pass
+    gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+
     // wireframes?
     //gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
 
-    let shader_program = Shader::new("shaders/vertex.glsl",
-                                     "shaders/fragment.glsl");
+    let shader_program = Shader::new(assets,
+                                     "shaders/vertex.glsl",
+                                     "shaders/fragment.glsl")?;
+    set_up_uniforms(&shader_program, proj);
 
+    Ok(shader_program)
+}
+
+// uniforms that never change, also needed after reloading the program
+unsafe fn set_up_uniforms(shader_program: &Shader, proj: Matrix4<f32>) {
     shader_program.use_program();
-    shader_program.set_vec3(c_str!("color"), vec3(0.8, 0.1, 0.5));
     shader_program.set_mat4(c_str!("proj"), proj);
-
-    shader_program
 }
 
 fn handle_window_event(window: &mut glfw::Window, event: glfw::WindowEvent) {
@@ -395,37 +888,65 @@ fn handle_window_event(window: &mut glfw::Window, event: glfw::WindowEvent) {
     }
 }
 
-#[allow(dead_code)]
-fn handle_input(window: &glfw::Window, camera: &mut Camera, speed: f32) {
-    let right = camera.dir.cross(camera.up).normalize();
-    let turn_speed = 60.0;
-
-    if window.get_key(Key::W) == Action::Press {
-        camera.pos += speed * camera.dir;
-    }
-    if window.get_key(Key::S) == Action::Press {
-        camera.pos -= speed * camera.dir;
-    }
-    if window.get_key(Key::A) == Action::Press {
-        camera.pos -= speed * right;
-    }
-    if window.get_key(Key::D) == Action::Press {
-        camera.pos += speed * right;
-    }
-    if window.get_key(Key::Up) == Action::Press {
-        camera.dir = Matrix3::from_axis_angle(right, Deg(speed * turn_speed))
-                   * camera.dir;
+#[cfg(test)]
+mod tests {
+    use cgmath::Point3;
+
+    use camera::Camera;
+    use easing::Easing;
+    use maze::Maze;
+    use walker::{Direction, Walker};
+    use super::{State, turn_back};
+
+    // a crossing at (1, 1), like a rat reaction right after it:
+    //       (0,1)
+    // (1,0) (1,1) (1,2)
+    //       (2,1)
+    fn crossing() -> Maze {
+        let mut maze = Maze::closed(3, 3);
+        maze.connect((1, 1), (0, 1));
+        maze.connect((1, 1), (1, 0));
+        maze.connect((1, 1), (1, 2));
+        maze.connect((1, 1), (2, 1));
+        maze
     }
-    if window.get_key(Key::Right) == Action::Press {
-        camera.dir = Matrix3::from_angle_y(Deg(speed * -turn_speed))
-                   * camera.dir;
-    }
-    if window.get_key(Key::Down) == Action::Press {
-        camera.dir = Matrix3::from_axis_angle(right, Deg(speed * -turn_speed))
-                   * camera.dir;
+
+    #[test]
+    fn turning_from_a_rat_retreats_across_a_junction() {
+        let maze = crossing();
+        // came in from the west and is about to go south, to the rat
+        let mut walker = Walker::new(&maze, 1, 0);
+        walker.step(Direction::East);
+        let mut camera = Camera::new(0, 0, Direction::South.to_vec());
+        camera.pos = walker.to_point();
+        walker.step(Direction::South);
+        let mut upcoming = None;
+
+        let state = turn_back(&mut walker, &mut upcoming, &camera, Easing::Linear);
+
+        assert_eq!(walker.pos(), (1, 0));
+        assert!(upcoming.is_none());
+        match state {
+            State::Turning(_) => {}
+            other => panic!("expected a turn, got {:?}", other)
+        }
     }
-    if window.get_key(Key::Left) == Action::Press {
-        camera.dir = Matrix3::from_angle_y(Deg(speed * turn_speed))
-                   * camera.dir;
+
+    #[test]
+    fn turning_after_a_corner_returns_to_its_cell_first() {
+        let maze = crossing();
+        let mut walker = Walker::new(&maze, 1, 0);
+        walker.step(Direction::East);
+        walker.step(Direction::South);
+        // at the end of the curve, on the edge between (1,1) and (2,1)
+        let mut camera = Camera::new(0, 0, Direction::South.to_vec());
+        camera.pos = Point3::new(1.5, 0.0, 2.0);
+        let mut upcoming = None;
+
+        turn_back(&mut walker, &mut upcoming, &camera, Easing::Linear);
+
+        assert_eq!(walker.pos(), (1, 1));
+        assert_eq!(walker.direction, Direction::North);
+        assert_eq!(upcoming.map(|w| w.pos()), Some((1, 0)));
     }
 }
@@ -1,3 +1,6 @@
+use std::collections::VecDeque;
+use std::usize;
+
 use rand;
 
 const N: u8 = 0b0001;
@@ -75,6 +78,59 @@ impl Maze {
     pub fn west(&self, i: usize, j: usize) -> bool {
         self.grid[i][j] & W == 0
     }
+
+    /// Cells reachable in one step from `(i, j)`.
+    pub fn neighbours(&self, i: usize, j: usize) -> Vec<(usize, usize)> {
+        let mut cells = Vec::new();
+        if !self.north(i, j) { cells.push((i - 1, j)); }
+        if !self.east(i, j) { cells.push((i, j + 1)); }
+        if !self.south(i, j) { cells.push((i + 1, j)); }
+        if !self.west(i, j) { cells.push((i, j - 1)); }
+        cells
+    }
+
+    /// Steps from `(i, j)` to every cell walking the corridors,
+    /// indexed like `grid`.
+    pub fn distances(&self, i: usize, j: usize) -> Vec<Vec<usize>> {
+        let mut distances = vec![vec![usize::MAX; self.width]; self.height];
+        let mut queue = VecDeque::new();
+
+        distances[i][j] = 0;
+        queue.push_back((i, j));
+
+        while let Some((i, j)) = queue.pop_front() {
+            for (ni, nj) in self.neighbours(i, j) {
+                if distances[ni][nj] == usize::MAX {
+                    distances[ni][nj] = distances[i][j] + 1;
+                    queue.push_back((ni, nj));
+                }
+            }
+        }
+
+        distances
+    }
+}
+
+#[cfg(test)]
+impl Maze {
+    /// A maze with every wall up, to carve by hand.
+    pub fn closed(width: usize, height: usize) -> Maze {
+        Maze {
+            grid: vec![vec![0; width]; height],
+            width: width,
+            height: height
+        }
+    }
+
+    /// Opens the wall between two neighbouring cells.
+    pub fn connect(&mut self, (i, j): (usize, usize), (ni, nj): (usize, usize)) {
+        let (d, back) = if ni < i { (N, S) }
+                        else if nj > j { (E, W) }
+                        else if ni > i { (S, N) }
+                        else { (W, E) };
+        self.grid[i][j] |= d;
+        self.grid[ni][nj] |= back;
+    }
 }
 
 fn carve_from(cx: usize, cy: usize, maze: &mut Maze) {
@@ -113,3 +169,56 @@ fn carve_from(cx: usize, cy: usize, maze: &mut Maze) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::usize;
+
+    use super::Maze;
+
+    #[test]
+    fn distances_are_steps_along_the_corridors() {
+        // a U around a wall, with the bottom right cell walled off:
+        // (0,0) (0,1) (0,2)
+        // (1,0)   |   (1,2)
+        // (2,0) (2,1)   x
+        let mut maze = Maze::closed(3, 3);
+        maze.connect((1, 0), (0, 0));
+        maze.connect((0, 0), (0, 1));
+        maze.connect((0, 1), (0, 2));
+        maze.connect((0, 2), (1, 2));
+        maze.connect((1, 0), (2, 0));
+        maze.connect((2, 0), (2, 1));
+        maze.connect((1, 0), (1, 1));
+
+        let distances = maze.distances(1, 0);
+
+        assert_eq!(distances[1][0], 0);
+        assert_eq!(distances[0][0], 1);
+        assert_eq!(distances[1][1], 1);
+        assert_eq!(distances[2][0], 1);
+        assert_eq!(distances[0][1], 2);
+        assert_eq!(distances[2][1], 2);
+        assert_eq!(distances[0][2], 3);
+        assert_eq!(distances[1][2], 4);
+        assert_eq!(distances[2][2], usize::MAX);
+    }
+
+    #[test]
+    fn distances_cover_a_carved_maze() {
+        let maze = Maze::new(8, 6);
+        let distances = maze.distances(0, 0);
+
+        // every cell is reachable, one step further than some neighbour
+        for i in 0..maze.height {
+            for j in 0..maze.width {
+                let d = distances[i][j];
+                assert!(d != usize::MAX);
+                if (i, j) != (0, 0) {
+                    assert!(maze.neighbours(i, j).iter()
+                            .any(|&(ni, nj)| distances[ni][nj] + 1 == d));
+                }
+            }
+        }
+    }
+}
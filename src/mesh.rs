@@ -0,0 +1,565 @@
+use std::fs;
+use std::path::Path;
+use std::collections::HashMap;
+
+use cgmath::{vec3, Vector3, InnerSpace};
+
+use assets::Assets;
+use error::Error;
+
+/// Triangles ready to be drawn, three vertices each, no indices.
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>
+}
+
+impl Mesh {
+    /// Loads a Wavefront OBJ or COLLADA file, by extension. `name` is
+    /// looked up as a file first and then as an asset.
+    pub fn load(assets: &Assets, name: &str) -> Result<Mesh, Error> {
+        let text = if Path::new(name).is_file() {
+            fs::read_to_string(name)
+                .map_err(|err| Error::Io { path: name.to_string(), err: err })?
+        } else {
+            assets.read_to_string(name)?
+        };
+
+        let mesh = if name.ends_with(".obj") {
+            Mesh::from_obj(&text)
+        } else if name.ends_with(".dae") {
+            Mesh::from_collada(&text)
+        } else {
+            Err("unknown format, expected .obj or .dae".to_string())
+        };
+
+        mesh.map_err(|message| Error::Mesh { path: name.to_string(), message: message })
+    }
+
+    /// Positions, normals and texture coordinates; polygons are
+    /// triangulated as fans and missing normals are made flat.
+    pub fn from_obj(text: &str) -> Result<Mesh, String> {
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut uvs = Vec::new();
+        let mut mesh = Mesh::default();
+        let mut smooth = true;
+
+        for (n, line) in text.lines().enumerate() {
+            let error = |message: &str| format!("line {}: {}", n + 1, message);
+            let mut words = line.split_whitespace();
+
+            match words.next() {
+                Some("v") => positions.push(floats3(words).ok_or_else(|| error("bad vertex"))?),
+                Some("vn") => normals.push(floats3(words).ok_or_else(|| error("bad normal"))?),
+                Some("vt") => {
+                    let uv: Vec<f32> = words.filter_map(|w| w.parse().ok()).collect();
+                    if uv.len() < 2 {
+                        return Err(error("bad texture coordinate"));
+                    }
+                    uvs.push([uv[0], uv[1]]);
+                }
+                Some("f") => {
+                    let mut corners = Vec::new();
+                    for word in words {
+                        corners.push(obj_corner(word, positions.len(), uvs.len(), normals.len())
+                                     .ok_or_else(|| error("bad face"))?);
+                    }
+                    if corners.len() < 3 {
+                        return Err(error("face with less than 3 vertices"));
+                    }
+
+                    for i in 1..corners.len() - 1 {
+                        for &(p, t, nor) in &[corners[0], corners[i], corners[i + 1]] {
+                            mesh.positions.push(positions[p]);
+                            mesh.uvs.push(t.map(|t| uvs[t]).unwrap_or([0.0, 0.0]));
+                            match nor {
+                                Some(nor) => mesh.normals.push(normals[nor]),
+                                None => smooth = false
+                            }
+                        }
+                    }
+                }
+                // objects, groups, materials... we just want the triangles
+                _ => {}
+            }
+        }
+
+        if !smooth {
+            mesh.normals = mesh.flat_normals();
+        }
+
+        mesh.check()
+    }
+
+    /// The first `<geometry>`, with its `<triangles>` or `<polylist>`
+    /// and their `VERTEX`, `NORMAL` and `TEXCOORD` inputs.
+    pub fn from_collada(text: &str) -> Result<Mesh, String> {
+        let root = xml::parse(text)?;
+
+        let mesh_node = root.find("library_geometries")
+            .and_then(|lib| lib.find("geometry"))
+            .and_then(|geometry| geometry.find("mesh"))
+            .ok_or("no <mesh> in <library_geometries>")?;
+
+        // sources by id, as flat float arrays with their stride
+        let mut sources = HashMap::new();
+        for source in mesh_node.children_named("source") {
+            let floats = source.find("float_array")
+                .ok_or("<source> without <float_array>")?
+                .text
+                .split_whitespace()
+                .map(|f| f.parse::<f32>().map_err(|_| format!("bad float `{}`", f)))
+                .collect::<Result<Vec<f32>, String>>()?;
+            let stride = source.find("technique_common")
+                .and_then(|t| t.find("accessor"))
+                .and_then(|a| a.attr("stride"))
+                .and_then(|s| s.parse::<usize>().ok())
+                .unwrap_or(3);
+            sources.insert(source.attr("id").unwrap_or(""), (floats, stride));
+        }
+
+        // <vertices> is just an alias to the positions source
+        let mut aliases = HashMap::new();
+        for vertices in mesh_node.children_named("vertices") {
+            if let Some(input) = vertices.children_named("input")
+                .find(|i| i.attr("semantic") == Some("POSITION")) {
+                aliases.insert(vertices.attr("id").unwrap_or(""),
+                               input.attr("source").unwrap_or("").trim_start_matches('#'));
+            }
+        }
+
+        let mut mesh = Mesh::default();
+        let mut smooth = true;
+
+        let primitives = mesh_node.children.iter()
+            .filter(|c| c.name == "triangles" || c.name == "polylist");
+        for primitive in primitives {
+            let mut inputs = Vec::new();
+            for input in primitive.children_named("input") {
+                let semantic = input.attr("semantic").unwrap_or("");
+                let offset = input.attr("offset").and_then(|o| o.parse::<usize>().ok()).unwrap_or(0);
+                let mut id = input.attr("source").unwrap_or("").trim_start_matches('#');
+                if let Some(alias) = aliases.get(id) {
+                    id = alias;
+                }
+                let source = sources.get(id).ok_or_else(|| format!("unknown source `{}`", id))?;
+                inputs.push((semantic, offset, source));
+            }
+
+            let stride = inputs.iter().map(|&(_, offset, _)| offset + 1).max().unwrap_or(1);
+            let indices = primitive.find("p")
+                .ok_or("primitive without <p>")?
+                .text
+                .split_whitespace()
+                .map(|i| i.parse::<usize>().map_err(|_| format!("bad index `{}`", i)))
+                .collect::<Result<Vec<usize>, String>>()?;
+
+            // polygon sizes, all triangles unless it's a polylist
+            let vcount = match primitive.find("vcount") {
+                Some(vcount) => vcount.text
+                    .split_whitespace()
+                    .map(|c| c.parse::<usize>().map_err(|_| format!("bad count `{}`", c)))
+                    .collect::<Result<Vec<usize>, String>>()?,
+                None => vec![3; indices.len() / stride / 3]
+            };
+
+            let fetch = |semantic: &str, vertex: usize, size: usize| -> Result<Option<Vec<f32>>, String> {
+                match inputs.iter().find(|&&(s, _, _)| s == semantic) {
+                    Some(&(_, offset, &(ref floats, source_stride))) => {
+                        let index = *indices.get(vertex * stride + offset)
+                            .ok_or("<p> is too short")?;
+                        let start = index * source_stride;
+                        floats.get(start..start + size)
+                            .map(|v| Some(v.to_vec()))
+                            .ok_or_else(|| format!("{} index {} out of range", semantic, index))
+                    }
+                    None => Ok(None)
+                }
+            };
+
+            let mut first = 0;
+            for &count in &vcount {
+                for i in 1..count.saturating_sub(1) {
+                    for &vertex in &[first, first + i, first + i + 1] {
+                        let p = fetch("VERTEX", vertex, 3)?.ok_or("no VERTEX input")?;
+                        mesh.positions.push([p[0], p[1], p[2]]);
+                        match fetch("NORMAL", vertex, 3)? {
+                            Some(nor) => mesh.normals.push([nor[0], nor[1], nor[2]]),
+                            None => smooth = false
+                        }
+                        let uv = fetch("TEXCOORD", vertex, 2)?.unwrap_or(vec![0.0, 0.0]);
+                        mesh.uvs.push([uv[0], uv[1]]);
+                    }
+                }
+                first += count;
+            }
+        }
+
+        if !smooth {
+            mesh.normals = mesh.flat_normals();
+        }
+
+        mesh.check()
+    }
+
+    /// Convex polygons given as indices into `vertices`, in any winding:
+    /// every face is turned to look away from the origin.
+    pub fn from_faces(vertices: &[Vector3<f32>], faces: &[Vec<usize>]) -> Mesh {
+        let mut mesh = Mesh::default();
+
+        for face in faces {
+            let corners: Vec<Vector3<f32>> = face.iter().map(|&i| vertices[i]).collect();
+            let mut normal = (corners[1] - corners[0]).cross(corners[2] - corners[0]).normalize();
+            let outwards = normal.dot(corners[0]) >= 0.0;
+            if !outwards {
+                normal = -normal;
+            }
+
+            for i in 1..corners.len() - 1 {
+                let tri = if outwards {
+                    [corners[0], corners[i], corners[i + 1]]
+                } else {
+                    [corners[0], corners[i + 1], corners[i]]
+                };
+                for v in tri.iter() {
+                    mesh.positions.push([v.x, v.y, v.z]);
+                    mesh.normals.push([normal.x, normal.y, normal.z]);
+                    mesh.uvs.push([0.0, 0.0]);
+                }
+            }
+        }
+
+        mesh
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// position, texture coordinates and normal of each vertex, one after the other.
+    pub fn interleaved(&self) -> Vec<f32> {
+        let mut data = Vec::with_capacity(self.vertex_count() * 8);
+        for i in 0..self.vertex_count() {
+            data.extend_from_slice(&self.positions[i]);
+            data.extend_from_slice(&self.uvs[i]);
+            data.extend_from_slice(&self.normals[i]);
+        }
+        data
+    }
+
+    // the normal of each triangle for its three vertices
+    fn flat_normals(&self) -> Vec<[f32; 3]> {
+        let mut normals = Vec::with_capacity(self.positions.len());
+        for tri in self.positions.chunks(3) {
+            let v = |p: [f32; 3]| vec3(p[0], p[1], p[2]);
+            let normal: Vector3<f32> = (v(tri[1]) - v(tri[0])).cross(v(tri[2]) - v(tri[0]));
+            let normal = if normal.magnitude2() > 0.0 { normal.normalize() } else { normal };
+            for _ in 0..3 {
+                normals.push([normal.x, normal.y, normal.z]);
+            }
+        }
+        normals
+    }
+
+    fn check(self) -> Result<Mesh, String> {
+        if self.positions.is_empty() {
+            Err("no triangles".to_string())
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+fn floats3<'a, I: Iterator<Item = &'a str>>(words: I) -> Option<[f32; 3]> {
+    let v: Vec<f32> = words.take(3).filter_map(|w| w.parse().ok()).collect();
+    if v.len() == 3 {
+        Some([v[0], v[1], v[2]])
+    } else {
+        None
+    }
+}
+
+// `v`, `v/vt`, `v//vn` or `v/vt/vn`, 1-based or negative (relative to the end)
+fn obj_corner(word: &str, positions: usize, uvs: usize, normals: usize)
+              -> Option<(usize, Option<usize>, Option<usize>)> {
+    let index = |s: Option<&str>, len: usize| -> Option<Option<usize>> {
+        match s {
+            None | Some("") => Some(None),
+            Some(s) => {
+                let i = s.parse::<i64>().ok()?;
+                let i = if i < 0 { len as i64 + i } else { i - 1 };
+                if 0 <= i && (i as usize) < len {
+                    Some(Some(i as usize))
+                } else {
+                    None
+                }
+            }
+        }
+    };
+
+    let mut parts = word.split('/');
+    let p = index(parts.next(), positions)??;
+    let t = index(parts.next(), uvs)?;
+    let n = index(parts.next(), normals)?;
+    Some((p, t, n))
+}
+
+// just enough XML for COLLADA files: elements, attributes and text,
+// skipping declarations and comments
+mod xml {
+    pub struct Element<'a> {
+        pub name: &'a str,
+        pub attrs: Vec<(&'a str, &'a str)>,
+        pub children: Vec<Element<'a>>,
+        pub text: String
+    }
+
+    impl<'a> Element<'a> {
+        pub fn attr(&self, name: &str) -> Option<&'a str> {
+            self.attrs.iter().find(|&&(k, _)| k == name).map(|&(_, v)| v)
+        }
+
+        pub fn find(&self, name: &str) -> Option<&Element<'a>> {
+            self.children.iter().find(|c| c.name == name)
+        }
+
+        pub fn children_named<'b>(&'b self, name: &'b str) -> impl Iterator<Item = &'b Element<'a>> {
+            self.children.iter().filter(move |c| c.name == name)
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Element, String> {
+        let mut stack = vec![Element {
+            name: "",
+            attrs: Vec::new(),
+            children: Vec::new(),
+            text: String::new()
+        }];
+
+        let mut rest = text;
+        while let Some(start) = rest.find('<') {
+            stack.last_mut().unwrap().text.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            if rest.starts_with("<!--") {
+                let end = rest.find("-->").ok_or("unterminated comment")?;
+                rest = &rest[end + 3..];
+                continue;
+            }
+
+            let end = rest.find('>').ok_or("unterminated tag")?;
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+
+            if tag.starts_with('?') || tag.starts_with('!') {
+                continue;
+            }
+
+            if tag.starts_with('/') {
+                let element = stack.pop().unwrap();
+                if element.name != tag[1..].trim() || stack.is_empty() {
+                    return Err(format!("unexpected </{}>", tag[1..].trim()));
+                }
+                stack.last_mut().unwrap().children.push(element);
+                continue;
+            }
+
+            let self_closing = tag.ends_with('/');
+            let tag = tag.trim_end_matches('/');
+            let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+
+            let element = Element {
+                name: &tag[..name_end],
+                attrs: attributes(&tag[name_end..]),
+                children: Vec::new(),
+                text: String::new()
+            };
+
+            if self_closing {
+                stack.last_mut().unwrap().children.push(element);
+            } else {
+                stack.push(element);
+            }
+        }
+
+        if stack.len() != 1 {
+            return Err(format!("unclosed <{}>", stack.last().unwrap().name));
+        }
+        let mut document = stack.pop().unwrap();
+        if document.children.len() == 1 {
+            Ok(document.children.pop().unwrap())
+        } else {
+            Err("expected a single root element".to_string())
+        }
+    }
+
+    // `key="value"` pairs, with single or double quotes
+    fn attributes(mut text: &str) -> Vec<(&str, &str)> {
+        let mut attrs = Vec::new();
+        while let Some(eq) = text.find('=') {
+            let key = text[..eq].trim();
+            let after = text[eq + 1..].trim_start();
+            let quote = match after.chars().next() {
+                Some(q) if q == '"' || q == '\'' => q,
+                _ => break
+            };
+            let value_end = match after[1..].find(quote) {
+                Some(end) => end + 1,
+                None => break
+            };
+            attrs.push((key, &after[1..value_end]));
+            text = &after[value_end + 1..];
+        }
+        attrs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Mesh;
+
+    const SQUARE: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n";
+
+    #[test]
+    fn obj_quads_are_fans() {
+        let mesh = Mesh::from_obj(&format!("{}f 1 2 3 4\n", SQUARE)).unwrap();
+
+        assert_eq!(mesh.positions, vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0],
+                                        [0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]]);
+    }
+
+    #[test]
+    fn obj_pentagons_are_fans() {
+        let text = format!("{}v 0.5 2 0\nf 1 2 3 5 4\n", SQUARE);
+        let mesh = Mesh::from_obj(&text).unwrap();
+
+        assert_eq!(mesh.vertex_count(), 9);
+        // every triangle starts at the first corner
+        for tri in mesh.positions.chunks(3) {
+            assert_eq!(tri[0], [0.0, 0.0, 0.0]);
+        }
+        assert_eq!(mesh.positions[8], [0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn obj_negative_indices_count_from_the_end() {
+        let absolute = Mesh::from_obj(&format!("{}f 2 3 4\n", SQUARE)).unwrap();
+        let relative = Mesh::from_obj(&format!("{}f -3 -2 -1\n", SQUARE)).unwrap();
+
+        assert_eq!(absolute.positions, relative.positions);
+    }
+
+    #[test]
+    fn obj_texture_coordinates_and_normals() {
+        let uvs = Mesh::from_obj(&format!("{}vt 0.25 0.75\nf 1/1 2/1 3/1\n", SQUARE)).unwrap();
+        let normals = Mesh::from_obj(&format!("{}vn 0 0 -1\nf 1//1 3//1 4//1\n", SQUARE)).unwrap();
+
+        assert_eq!(uvs.uvs, vec![[0.25, 0.75]; 3]);
+        // missing ones are zero
+        assert_eq!(normals.uvs, vec![[0.0, 0.0]; 3]);
+        // the given normals are used as is
+        assert_eq!(normals.normals, vec![[0.0, 0.0, -1.0]; 3]);
+    }
+
+    #[test]
+    fn obj_missing_normals_are_flat() {
+        let mesh = Mesh::from_obj(&format!("{}f 1 2 3\n", SQUARE)).unwrap();
+
+        assert_eq!(mesh.normals, vec![[0.0, 0.0, 1.0]; 3]);
+    }
+
+    #[test]
+    fn obj_bad_indices_are_errors() {
+        for face in &["f 1 2 5", "f 0 1 2", "f -5 1 2", "f 1/2 2 3", "f 1//1 2 3", "f 1 x 3", "f 1 2"] {
+            let result = Mesh::from_obj(&format!("{}{}\n", SQUARE, face));
+            assert!(result.is_err(), "{} gave {:?}", face, result);
+        }
+    }
+
+    // a square with a normal and texture coordinates, and `primitive`
+    // using them
+    fn collada(primitive: &str) -> String {
+        format!(r##"<?xml version="1.0" encoding="utf-8"?>
+<COLLADA>
+  <!-- just a square -->
+  <library_geometries>
+    <geometry id="square">
+      <mesh>
+        <source id="pos">
+          <float_array id="pos-array" count="12">0 0 0  1 0 0  1 1 0  0 1 0</float_array>
+          <technique_common><accessor source="#pos-array" count="4" stride="3"/></technique_common>
+        </source>
+        <source id="nor">
+          <float_array id="nor-array" count="3">0 0 1</float_array>
+        </source>
+        <source id="uv">
+          <float_array id="uv-array" count="8">0 0 1 0 1 1 0 1</float_array>
+          <technique_common><accessor source="#uv-array" count="4" stride='2'/></technique_common>
+        </source>
+        <vertices id="verts">
+          <input semantic="POSITION" source="#pos"/>
+        </vertices>
+        {}
+      </mesh>
+    </geometry>
+  </library_geometries>
+</COLLADA>"##, primitive)
+    }
+
+    #[test]
+    fn collada_triangles() {
+        let mesh = Mesh::from_collada(&collada(r##"<triangles count="2">
+          <input semantic="VERTEX" source="#verts" offset="0"/>
+          <input semantic="NORMAL" source="#nor" offset="1"/>
+          <input semantic="TEXCOORD" source="#uv" offset="2"/>
+          <p>0 0 0 1 0 1 2 0 2  0 0 0 2 0 2 3 0 3</p>
+        </triangles>"##)).unwrap();
+
+        assert_eq!(mesh.positions, vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0],
+                                        [0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]]);
+        assert_eq!(mesh.normals, vec![[0.0, 0.0, 1.0]; 6]);
+        assert_eq!(mesh.uvs[2], [1.0, 1.0]);
+        assert_eq!(mesh.uvs[5], [0.0, 1.0]);
+    }
+
+    #[test]
+    fn collada_polylists_are_fans() {
+        let triangles = Mesh::from_collada(&collada(r##"<triangles count="2">
+          <input semantic="VERTEX" source="#verts" offset="0"/>
+          <p>0 1 2 0 2 3</p>
+        </triangles>"##)).unwrap();
+        let polylist = Mesh::from_collada(&collada(r##"<polylist count="1">
+          <input semantic="VERTEX" source="#verts" offset="0"/>
+          <vcount>4</vcount>
+          <p>0 1 2 3</p>
+        </polylist>"##)).unwrap();
+
+        assert_eq!(triangles.positions, polylist.positions);
+        // no NORMAL input, so flat ones
+        assert_eq!(polylist.normals, vec![[0.0, 0.0, 1.0]; 6]);
+        assert_eq!(polylist.uvs, vec![[0.0, 0.0]; 6]);
+    }
+
+    #[test]
+    fn collada_bad_documents_are_errors() {
+        let bad = [
+            // not even XML
+            "<COLLADA><library_geometries>".to_string(),
+            "<COLLADA></library_geometries>".to_string(),
+            "<a/><b/>".to_string(),
+            // XML but no mesh
+            "<COLLADA><library_geometries/></COLLADA>".to_string(),
+            // index out of range, of a source that doesn't exist, or cut short
+            collada(r##"<triangles><input semantic="VERTEX" source="#verts" offset="0"/><p>0 1 4</p></triangles>"##),
+            collada(r##"<triangles><input semantic="VERTEX" source="#nope" offset="0"/><p>0 1 2</p></triangles>"##),
+            collada(r##"<polylist><input semantic="VERTEX" source="#verts" offset="0"/><vcount>4</vcount><p>0 1 2</p></polylist>"##),
+            collada(r##"<triangles><input semantic="VERTEX" source="#verts" offset="0"/><p>0 one 2</p></triangles>"##)
+        ];
+
+        for text in bad.iter() {
+            assert!(Mesh::from_collada(text).is_err(), "{}", text);
+        }
+    }
+}
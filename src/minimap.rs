@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::mem;
+use std::ptr;
+
+use cgmath::prelude::*;
+use cgmath::{Matrix4, Vector2, vec2, vec3, Vector3};
+use gl;
+use gl::types::*;
+
+use assets::Assets;
+use camera::Camera;
+use error::Error;
+use ico::Ico;
+use maze::Maze;
+use rat::Rat;
+use shader::Shader;
+use smiley::Smiley;
+
+// position and color
+const STRIDE: usize = 6;
+// in cells
+const WALL_WIDTH: f32 = 0.1;
+const DOT_SIZE: f32 = 0.4;
+const ARROW_SIZE: f32 = 0.8;
+// in screen heights
+const CORNER_SIZE: f32 = 0.3;
+const MARGIN: f32 = 0.025;
+
+const BACKGROUND: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const WALL: [f32; 4] = [0.9, 0.9, 0.9, 1.0];
+const RAT: [f32; 4] = [0.55, 0.4, 0.3, 1.0];
+const SMILEY: [f32; 4] = [1.0, 0.85, 0.0, 1.0];
+const CAMERA: [f32; 4] = [1.0, 0.2, 0.2, 1.0];
+
+/// Where the map is shown, if at all.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum View {
+    Hidden,
+    /// Small, in the top right corner.
+    Corner,
+    /// Covering the whole screen.
+    Full
+}
+
+/// An overhead map of the maze drawn over the scene.
+pub struct Minimap {
+    pub view: View,
+    shader: Shader,
+    // background and walls, only built when the maze changes
+    walls: Buffer,
+    wall_vertices: GLint,
+    // solids, rats, the smiley and the camera, streamed every frame
+    dots: Buffer
+}
+
+struct Buffer {
+    vao: u32,
+    vbo: u32
+}
+
+impl View {
+    /// The next one, to cycle through them with a single key.
+    pub fn next(&self) -> View {
+        match *self {
+            View::Hidden => View::Corner,
+            View::Corner => View::Full,
+            View::Full => View::Hidden
+        }
+    }
+}
+
+impl Buffer {
+    unsafe fn new() -> Buffer {
+        let (mut vao, mut vbo) = (0, 0);
+
+        //* generate VAO, VBO
+        gl::GenVertexArrays(1, &mut vao);
+        gl::GenBuffers(1, &mut vbo);
+
+        //* binding VAO
+        gl::BindVertexArray(vao);
+
+        //* VBO data, filled in by `upload`
+        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+
+        //* vertex attribs
+        // aPos = 0
+        gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE,
+                                (STRIDE * mem::size_of::<GLfloat>()) as GLint,
+                                ptr::null());
+        gl::EnableVertexAttribArray(0);
+        // aColor = 1
+        gl::VertexAttribPointer(1, 4, gl::FLOAT, gl::FALSE,
+                                (STRIDE * mem::size_of::<GLfloat>()) as GLint,
+                                (2 * mem::size_of::<GLfloat>()) as *const GLvoid);
+        gl::EnableVertexAttribArray(1);
+
+        Buffer {
+            vao: vao,
+            vbo: vbo
+        }
+    }
+
+    unsafe fn upload(&self, vertices: &[f32], usage: GLenum) {
+        gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+        gl::BufferData(gl::ARRAY_BUFFER,
+                       (vertices.len() * mem::size_of::<GLfloat>()) as isize,
+                       vertices.as_ptr() as *const _,
+                       usage);
+    }
+
+    unsafe fn draw(&self, count: GLint) {
+        gl::BindVertexArray(self.vao);
+        gl::DrawArrays(gl::TRIANGLES, 0, count);
+    }
+}
+
+impl Minimap {
+    pub unsafe fn new(assets: &Assets) -> Result<Minimap, Error> {
+        let shader = Shader::new(assets,
+                                 "shaders/minimap/vertex.glsl",
+                                 "shaders/minimap/fragment.glsl")?;
+
+        Ok(Minimap {
+            view: View::Hidden,
+            shader: shader,
+            walls: Buffer::new(),
+            wall_vertices: 0,
+            dots: Buffer::new()
+        })
+    }
+
+    pub unsafe fn reload_if_changed(&mut self) {
+        self.shader.reload_if_changed();
+    }
+
+    /// Builds the walls of a new maze.
+    pub unsafe fn set_maze(&mut self, maze: &Maze) {
+        let vertices = gen_walls(maze);
+        self.walls.upload(&vertices, gl::STATIC_DRAW);
+        self.wall_vertices = (vertices.len() / STRIDE) as GLint;
+    }
+
+    /// Draws the walls and solids, rats, the smiley and the camera on top
+    /// of whatever is on screen, `ratio` being its width over its height.
+    pub unsafe fn draw(&self,
+                       ratio: f32,
+                       maze: &Maze,
+                       icos: &HashMap<(usize, usize), Ico>,
+                       rats: &[Rat],
+                       smiley: &Smiley,
+                       camera: &Camera) {
+        let (size, center) = match self.view {
+            View::Hidden => return,
+            View::Corner => {
+                let size = CORNER_SIZE * 2.0;
+                let half = MARGIN * 2.0 + size / 2.0;
+                (size, vec2(1.0 - half / ratio, 1.0 - half))
+            }
+            View::Full => (2.0 - MARGIN * 4.0, vec2(0.0, 0.0))
+        };
+
+        let vertices = gen_dots(icos, rats, smiley, camera);
+        self.dots.upload(&vertices, gl::STREAM_DRAW);
+
+        gl::Disable(gl::DEPTH_TEST);
+        gl::Enable(gl::BLEND);
+
+        self.shader.use_program();
+        self.shader.set_mat4(c_str!("transform"), transform(maze, size, center, ratio));
+
+        self.walls.draw(self.wall_vertices);
+        self.dots.draw((vertices.len() / STRIDE) as GLint);
+
+        gl::Disable(gl::BLEND);
+        gl::Enable(gl::DEPTH_TEST);
+    }
+}
+
+// from cells, x right and y down, to a square `size` tall around `center`
+fn transform(maze: &Maze, size: f32, center: Vector2<f32>, ratio: f32) -> Matrix4<f32> {
+    let (w, h) = (maze.width as f32, maze.height as f32);
+    let scale = size / w.max(h);
+    Matrix4::from_translation(vec3(center.x, center.y, 0.0))
+        * Matrix4::from_nonuniform_scale(scale / ratio, -scale, 1.0)
+        * Matrix4::from_translation(vec3(-w / 2.0, -h / 2.0, 0.0))
+}
+
+fn gen_walls(maze: &Maze) -> Vec<f32> {
+    let mut vertices = Vec::new();
+    let (w, h) = (maze.width as f32, maze.height as f32);
+
+    rect(&mut vertices, vec2(0.0, 0.0), vec2(w, h), BACKGROUND);
+
+    // each cell draws its north and west walls, the last ones close the maze
+    let half = WALL_WIDTH / 2.0;
+    for i in 0..maze.height {
+        for j in 0..maze.width {
+            let (x, y) = (j as f32, i as f32);
+            if maze.north(i, j) {
+                rect(&mut vertices, vec2(x - half, y - half), vec2(x + 1.0 + half, y + half), WALL);
+            }
+            if maze.west(i, j) {
+                rect(&mut vertices, vec2(x - half, y - half), vec2(x + half, y + 1.0 + half), WALL);
+            }
+            if i == maze.height - 1 && maze.south(i, j) {
+                rect(&mut vertices, vec2(x - half, h - half), vec2(x + 1.0 + half, h + half), WALL);
+            }
+            if j == maze.width - 1 && maze.east(i, j) {
+                rect(&mut vertices, vec2(w - half, y - half), vec2(w + half, y + 1.0 + half), WALL);
+            }
+        }
+    }
+
+    vertices
+}
+
+fn gen_dots(icos: &HashMap<(usize, usize), Ico>,
+            rats: &[Rat],
+            smiley: &Smiley,
+            camera: &Camera) -> Vec<f32> {
+    let mut vertices = Vec::new();
+
+    for ico in icos.values() {
+        let d = ico.material.diffuse;
+        dot(&mut vertices, vec2(ico.pos.x, ico.pos.z), [d.x, d.y, d.z, 1.0]);
+    }
+    for rat in rats {
+        dot(&mut vertices, vec2(rat.pos.x, rat.pos.z), RAT);
+    }
+    let (i, j) = smiley.pos();
+    dot(&mut vertices, vec2(j as f32 + 0.5, i as f32 + 0.5), SMILEY);
+
+    arrow(&mut vertices, vec2(camera.pos.x, camera.pos.z), camera.dir);
+
+    vertices
+}
+
+fn vertex(vertices: &mut Vec<f32>, p: Vector2<f32>, color: [f32; 4]) {
+    vertices.extend_from_slice(&[p.x, p.y]);
+    vertices.extend_from_slice(&color);
+}
+
+fn rect(vertices: &mut Vec<f32>, min: Vector2<f32>, max: Vector2<f32>, color: [f32; 4]) {
+    for &(x, y) in &[(min.x, min.y), (max.x, min.y), (max.x, max.y),
+                     (min.x, min.y), (max.x, max.y), (min.x, max.y)] {
+        vertex(vertices, vec2(x, y), color);
+    }
+}
+
+fn dot(vertices: &mut Vec<f32>, center: Vector2<f32>, color: [f32; 4]) {
+    let half = vec2(DOT_SIZE, DOT_SIZE) / 2.0;
+    rect(vertices, center - half, center + half, color);
+}
+
+// a triangle pointing where the camera looks
+fn arrow(vertices: &mut Vec<f32>, center: Vector2<f32>, dir: Vector3<f32>) {
+    let forward = vec2(dir.x, dir.z);
+    // looking straight up or down, as the free-fly camera can
+    let forward = if forward.magnitude2() > 0.0 { forward.normalize() } else { vec2(0.0, -1.0) };
+    let side = vec2(-forward.y, forward.x);
+    let size = ARROW_SIZE / 2.0;
+
+    vertex(vertices, center + forward * size, CAMERA);
+    vertex(vertices, center - forward * size + side * size * 0.7, CAMERA);
+    vertex(vertices, center - forward * size - side * size * 0.7, CAMERA);
+}
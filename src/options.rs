@@ -0,0 +1,138 @@
+use std::env;
+use std::path::PathBuf;
+
+use postprocess::EffectSpec;
+use texture::Filter;
+use effect::Weights;
+use rat::Reaction;
+use spawner::Rates;
+use easing::Easing;
+use player::Movement;
+
+// corner radius limits for --smooth, in cells: almost a sharp turn,
+// and a quarter circle from the middle of one wall to the next
+const MIN_RADIUS: f32 = 0.05;
+const MAX_RADIUS: f32 = 0.5;
+
+/// Command line options.
+#[derive(Debug)]
+pub struct Options {
+    pub fullscreen: bool,
+    pub post: Vec<EffectSpec>,
+    pub assets: Option<PathBuf>,
+    pub theme: String,
+    pub filter: Option<Filter>,
+    pub mesh: Option<String>,
+    pub effects: Weights,
+    pub rat_reaction: Reaction,
+    pub spawn: Rates,
+    pub smooth: Option<f32>,
+    pub easing: Easing,
+    pub play: Option<Movement>
+}
+
+impl Options {
+    pub fn from_args() -> Options {
+        let mut options = Options {
+            fullscreen: false,
+            post: Vec::new(),
+            assets: None,
+            theme: "classic".to_string(),
+            filter: None,
+            mesh: None,
+            effects: Weights::default(),
+            rat_reaction: Reaction::Pass,
+            spawn: Rates::default(),
+            smooth: None,
+            easing: Easing::EaseInOut,
+            play: None
+        };
+
+        let mut args = env::args().skip(1).peekable();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--fullscreen" => options.fullscreen = true,
+                // comma separated list of effects, applied in order
+                "--post" => {
+                    let value = args.next().unwrap_or_default();
+                    for spec in value.split(',').filter(|s| !s.is_empty()) {
+                        match EffectSpec::parse(spec) {
+                            Some(effect) => options.post.push(effect),
+                            None => println!("Ignoring bad effect: {}", spec)
+                        }
+                    }
+                }
+                // directory with files overriding the embedded assets
+                "--assets" => options.assets = args.next().map(PathBuf::from),
+                // theme name or directory with a theme.txt
+                "--theme" => {
+                    if let Some(theme) = args.next() {
+                        options.theme = theme;
+                    }
+                }
+                // texture filtering for every texture of the theme
+                "--filter" => {
+                    let value = args.next().unwrap_or_default();
+                    match Filter::parse(&value) {
+                        Some(filter) => options.filter = Some(filter),
+                        None => println!("Ignoring bad filter: {}", value)
+                    }
+                }
+                // .obj or .dae file drawn instead of every solid
+                "--mesh" => options.mesh = args.next(),
+                // how likely each effect of walking into a solid is
+                "--effects" => {
+                    let value = args.next().unwrap_or_default();
+                    match Weights::parse(&value) {
+                        Ok(weights) => options.effects = weights,
+                        Err(err) => println!("Ignoring bad effects {}: {}", value, err)
+                    }
+                }
+                // what to do when a rat is in the way
+                "--rat-reaction" => {
+                    let value = args.next().unwrap_or_default();
+                    match Reaction::parse(&value) {
+                        Some(reaction) => options.rat_reaction = reaction,
+                        None => println!("Ignoring bad rat reaction: {}", value)
+                    }
+                }
+                // densities and rate of solids and rats
+                "--spawn" => {
+                    let value = args.next().unwrap_or_default();
+                    match Rates::parse(&value) {
+                        Ok(rates) => options.spawn = rates,
+                        Err(err) => println!("Ignoring bad spawn rates {}: {}", value, err)
+                    }
+                }
+                // round corners, optionally followed by the radius in cells
+                "--smooth" => {
+                    let radius = args.peek().and_then(|r| r.parse::<f32>().ok());
+                    if radius.is_some() {
+                        args.next();
+                    }
+                    let radius = radius.unwrap_or(MAX_RADIUS);
+                    options.smooth = Some(radius.max(MIN_RADIUS).min(MAX_RADIUS));
+                }
+                // how turns and rolls speed up and slow down
+                "--easing" => {
+                    let value = args.next().unwrap_or_default();
+                    match Easing::parse(&value) {
+                        Some(easing) => options.easing = easing,
+                        None => println!("Ignoring bad easing: {}", value)
+                    }
+                }
+                // drive the camera with the keyboard, free movement by default
+                "--play" => {
+                    let movement = args.peek().and_then(|m| Movement::parse(m));
+                    if movement.is_some() {
+                        args.next();
+                    }
+                    options.play = Some(movement.unwrap_or(Movement::Free));
+                }
+                _ => println!("Ignoring unknown option: {}", arg)
+            }
+        }
+
+        options
+    }
+}
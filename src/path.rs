@@ -0,0 +1,52 @@
+use cgmath::{Point3, Vector3, EuclideanSpace, InnerSpace, MetricSpace};
+
+// segments used to measure the curve
+const SAMPLES: usize = 16;
+
+/// A corner of the maze rounded with a quadratic Bézier curve, from
+/// `radius` before the center of the cell to `radius` after it.
+#[derive(Debug, Clone, Copy)]
+pub struct Corner {
+    pub start: Point3<f32>,
+    pub control: Point3<f32>,
+    pub end: Point3<f32>,
+    length: f32
+}
+
+impl Corner {
+    /// `from` and `to` are the directions before and after the corner.
+    pub fn new(center: Point3<f32>, from: Vector3<f32>, to: Vector3<f32>, radius: f32) -> Corner {
+        let mut corner = Corner {
+            start: center - from * radius,
+            control: center,
+            end: center + to * radius,
+            length: 0.0
+        };
+
+        corner.length = (0..SAMPLES)
+            .map(|k| {
+                let (t0, t1) = (k as f32 / SAMPLES as f32, (k + 1) as f32 / SAMPLES as f32);
+                corner.point(t0).distance(corner.point(t1))
+            })
+            .sum();
+
+        corner
+    }
+
+    pub fn point(&self, t: f32) -> Point3<f32> {
+        let s = 1.0 - t;
+        Point3::from_vec(self.start.to_vec() * (s * s) +
+                         self.control.to_vec() * (2.0 * s * t) +
+                         self.end.to_vec() * (t * t))
+    }
+
+    /// Where the curve is heading at `t`.
+    pub fn tangent(&self, t: f32) -> Vector3<f32> {
+        let d = (self.control - self.start) * (1.0 - t) + (self.end - self.control) * t;
+        d.normalize()
+    }
+
+    pub fn length(&self) -> f32 {
+        self.length
+    }
+}
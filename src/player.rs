@@ -0,0 +1,151 @@
+use cgmath::prelude::*;
+use cgmath::{Matrix3, Point3, vec3, Vector3, Rad};
+use glfw::{Action, Key, Window};
+
+use camera::Camera;
+use easing::{Easing, Tween};
+use maze::Maze;
+use walker::Walker;
+
+const WALK_SPEED: f32 = 1.5;
+const TURN_SPEED: f32 = 2.0;
+// how close to a wall the camera can get
+const RADIUS: f32 = 0.2;
+
+/// How the keys move the camera in play mode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Movement {
+    /// A cell at a time, turning a quarter at a time like the walker.
+    Grid,
+    /// Anywhere on the floor, sliding along the walls.
+    Free
+}
+
+/// Drives the camera with the keyboard instead of the walker.
+pub struct Player<'a> {
+    movement: Movement,
+    // where it is and faces, grid movement only
+    walker: Walker<'a>,
+    turning: Option<Tween>,
+    moving: bool
+}
+
+impl Movement {
+    pub fn parse(name: &str) -> Option<Movement> {
+        match name {
+            "grid" => Some(Movement::Grid),
+            "free" => Some(Movement::Free),
+            _ => None
+        }
+    }
+}
+
+impl<'a> Player<'a> {
+    pub fn new(movement: Movement, walker: Walker<'a>) -> Player<'a> {
+        Player {
+            movement: movement,
+            walker: walker,
+            turning: None,
+            moving: false
+        }
+    }
+
+    pub fn update(&mut self, window: &Window, camera: &mut Camera,
+                  maze: &Maze, easing: Easing, dt: f32) {
+        match self.movement {
+            Movement::Grid => self.update_grid(window, camera, easing, dt),
+            Movement::Free => update_free(window, camera, maze, dt)
+        }
+    }
+
+    // finishes the current step or turn before taking the next key
+    fn update_grid(&mut self, window: &Window, camera: &mut Camera,
+                   easing: Easing, dt: f32) {
+        if let Some(mut tween) = self.turning.take() {
+            tween.advance(dt);
+            camera.turn(&tween);
+            if tween.done() {
+                camera.dir = self.walker.direction.to_vec();
+            } else {
+                self.turning = Some(tween);
+            }
+            return;
+        }
+        if self.moving {
+            self.moving = !camera.move_to(self.walker.to_point(), dt);
+            return;
+        }
+
+        let ahead = self.walker.direction;
+        let next = if pressed(window, &[Key::W, Key::Up]) {
+            if self.walker.options().contains(&ahead) {
+                self.walker.step(ahead);
+                self.moving = true;
+            }
+            return;
+        } else if pressed(window, &[Key::S, Key::Down]) {
+            ahead.opposite()
+        } else if pressed(window, &[Key::A, Key::Left]) {
+            ahead.left()
+        } else if pressed(window, &[Key::D, Key::Right]) {
+            ahead.left().opposite()
+        } else {
+            return;
+        };
+
+        self.walker.direction = next;
+        self.turning = Some(camera.turn_towards(next.to_vec(), easing));
+    }
+}
+
+// WASD walks and strafes, the arrows walk and turn
+fn update_free(window: &Window, camera: &mut Camera, maze: &Maze, dt: f32) {
+    let right = camera.dir.cross(camera.up).normalize();
+    let mut step = vec3(0.0, 0.0, 0.0);
+    let mut turn = 0.0;
+
+    if pressed(window, &[Key::W, Key::Up]) { step += camera.dir; }
+    if pressed(window, &[Key::S, Key::Down]) { step -= camera.dir; }
+    if pressed(window, &[Key::A]) { step -= right; }
+    if pressed(window, &[Key::D]) { step += right; }
+    if pressed(window, &[Key::Left]) { turn += 1.0; }
+    if pressed(window, &[Key::Right]) { turn -= 1.0; }
+
+    camera.dir = Matrix3::from_angle_y(Rad(turn * TURN_SPEED * dt)) * camera.dir;
+
+    step.y = 0.0;
+    if step.magnitude2() > 0.0 {
+        // never further than the radius, or it could skip a wall
+        let length = (WALK_SPEED * camera.speed * dt).min(RADIUS);
+        camera.pos = slide(maze, camera.pos, step.normalize() * length);
+    }
+}
+
+fn pressed(window: &Window, keys: &[Key]) -> bool {
+    keys.iter().any(|&key| window.get_key(key) == Action::Press)
+}
+
+/// Where moving `step` from `pos` ends up, stopping at the walls of
+/// the cell it starts in and sliding along them.
+fn slide(maze: &Maze, pos: Point3<f32>, step: Vector3<f32>) -> Point3<f32> {
+    let i = (pos.z.max(0.0) as usize).min(maze.height - 1);
+    let j = (pos.x.max(0.0) as usize).min(maze.width - 1);
+    let (top, left) = (i as f32, j as f32);
+    let mut pos = pos + step;
+
+    if maze.north(i, j) { pos.z = pos.z.max(top + RADIUS); }
+    if maze.south(i, j) { pos.z = pos.z.min(top + 1.0 - RADIUS); }
+    if maze.west(i, j) { pos.x = pos.x.max(left + RADIUS); }
+    if maze.east(i, j) { pos.x = pos.x.min(left + 1.0 - RADIUS); }
+
+    // in a perfect maze some wall ends at every corner, keep out of them
+    for &(z, x) in &[(top, left), (top, left + 1.0), (top + 1.0, left), (top + 1.0, left + 1.0)] {
+        let offset = vec3(pos.x - x, 0.0, pos.z - z);
+        let distance = offset.magnitude();
+        if distance < RADIUS && distance > 0.0 {
+            pos = Point3::new(x, pos.y, z) + offset * (RADIUS / distance);
+        }
+    }
+
+    pos
+}
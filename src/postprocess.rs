@@ -0,0 +1,274 @@
+use std::mem;
+use std::ptr;
+use std::ffi::{CStr, CString};
+
+use gl;
+use gl::types::*;
+use cgmath::vec2;
+
+use shader::Shader;
+use error::Error;
+use assets::Assets;
+
+// a quad covering the whole screen in NDC, no flipping needed here
+// because the texture comes from our own framebuffer
+const VERTICES: [f32; 16] = [
+     1.0, -1.0,  1.0, 0.0, // bottom right
+    -1.0, -1.0,  0.0, 0.0, // bottom left
+    -1.0,  1.0,  0.0, 1.0, // top left
+     1.0,  1.0,  1.0, 1.0, // top right
+];
+const INDICES: [u32; 6] = [
+    0, 1, 3,
+    1, 2, 3
+];
+
+// texture unit used to sample the previous pass,
+// far from the ones used by the maze textures
+const SCREEN_UNIT: u32 = 15;
+
+/// An effect as given in the command line, like `vignette:strength=0.8`.
+#[derive(Debug, Clone)]
+pub struct EffectSpec {
+    pub name: String,
+    pub params: Vec<(String, f32)>
+}
+
+struct Pass {
+    shader: Shader,
+    params: Vec<(CString, f32)>
+}
+
+struct Framebuffer {
+    fbo: GLuint,
+    color: GLuint
+}
+
+pub struct PostProcessor {
+    passes: Vec<Pass>,
+    targets: Vec<Framebuffer>,
+    width: i32,
+    height: i32,
+    vao: GLuint
+}
+
+impl EffectSpec {
+    /// Parses `name[:key=value[:key=value...]]`.
+    pub fn parse(spec: &str) -> Option<EffectSpec> {
+        let mut parts = spec.split(':');
+        let name = match parts.next() {
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => return None
+        };
+
+        let mut params = Vec::new();
+        for part in parts {
+            let mut kv = part.splitn(2, '=');
+            let key = kv.next().unwrap_or("");
+            let value = kv.next().and_then(|v| v.parse::<f32>().ok());
+            match value {
+                Some(value) if !key.is_empty() => {
+                    params.push((key.to_string(), value))
+                }
+                _ => return None
+            }
+        }
+
+        Some(EffectSpec {
+            name: name,
+            params: params
+        })
+    }
+}
+
+impl Framebuffer {
+    unsafe fn new(width: i32, height: i32) -> Framebuffer {
+        let (mut fbo, mut color, mut depth) = (0, 0, 0);
+
+        gl::GenFramebuffers(1, &mut fbo);
+        gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+
+        //* color attachment
+        gl::GenTextures(1, &mut color);
+        gl::BindTexture(gl::TEXTURE_2D, color);
+        gl::TexImage2D(gl::TEXTURE_2D,
+                       0,
+                       gl::RGB as i32,
+                       width,
+                       height,
+                       0,
+                       gl::RGB,
+                       gl::UNSIGNED_BYTE,
+                       ptr::null());
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+        gl::FramebufferTexture2D(gl::FRAMEBUFFER,
+                                 gl::COLOR_ATTACHMENT0,
+                                 gl::TEXTURE_2D,
+                                 color,
+                                 0);
+
+        //* depth attachment, only the scene pass needs it
+        // but it is simpler to keep both targets alike
+        gl::GenRenderbuffers(1, &mut depth);
+        gl::BindRenderbuffer(gl::RENDERBUFFER, depth);
+        gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH24_STENCIL8, width, height);
+        gl::FramebufferRenderbuffer(gl::FRAMEBUFFER,
+                                    gl::DEPTH_STENCIL_ATTACHMENT,
+                                    gl::RENDERBUFFER,
+                                    depth);
+
+        if gl::CheckFramebufferStatus(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
+            println!("Post-processing framebuffer is not complete.");
+        }
+
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+
+        Framebuffer {
+            fbo: fbo,
+            color: color
+        }
+    }
+}
+
+impl PostProcessor {
+
+    pub unsafe fn new(assets: &Assets,
+                      effects: &[EffectSpec],
+                      width: i32,
+                      height: i32) -> Result<PostProcessor, Error> {
+
+        let mut passes = Vec::new();
+        for effect in effects {
+            let fragment_path = format!("shaders/post/{}.glsl", effect.name);
+            let shader = Shader::new(assets, "shaders/post/vertex.glsl", &fragment_path)?;
+
+            let params = effect.params.iter()
+                .map(|&(ref key, value)| (CString::new(key.as_bytes()).unwrap(), value))
+                .collect();
+
+            passes.push(Pass {
+                shader: shader,
+                params: params
+            });
+        }
+
+        // nothing to do, render straight to the screen
+        if passes.is_empty() {
+            return Ok(PostProcessor {
+                passes: passes,
+                targets: Vec::new(),
+                width: width,
+                height: height,
+                vao: 0
+            });
+        }
+
+        // ping-pong between two targets, a single pass only needs one
+        let count = if passes.len() > 1 { 2 } else { 1 };
+        let targets = (0..count).map(|_| Framebuffer::new(width, height)).collect();
+
+        let (mut vao, mut vbo, mut ebo) = (0, 0, 0);
+
+        //* generate VAO, VBO, EBO
+        gl::GenVertexArrays(1, &mut vao);
+        gl::GenBuffers(1, &mut vbo);
+        gl::GenBuffers(1, &mut ebo);
+
+        //* binding VAO
+        gl::BindVertexArray(vao);
+
+        //* VBO data
+        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+        gl::BufferData(gl::ARRAY_BUFFER,
+                       mem::size_of::<[f32; 16]>() as isize,
+                       VERTICES.as_ptr() as *const _,
+                       gl::STATIC_DRAW);
+
+        //* EBO data
+        gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
+        gl::BufferData(gl::ELEMENT_ARRAY_BUFFER,
+                       mem::size_of::<[u32; 6]>() as isize,
+                       INDICES.as_ptr() as *const _,
+                       gl::STATIC_DRAW);
+
+        //* vertex attribs
+        // aPos = 0
+        gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE,
+                                4 * mem::size_of::<GLfloat>() as GLint,
+                                ptr::null());
+        gl::EnableVertexAttribArray(0);
+        // aTex = 1
+        gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE,
+                                4 * mem::size_of::<GLfloat>() as GLint,
+                                (2 * mem::size_of::<GLfloat>()) as *const GLvoid);
+        gl::EnableVertexAttribArray(1);
+
+        Ok(PostProcessor {
+            passes: passes,
+            targets: targets,
+            width: width,
+            height: height,
+            vao: vao
+        })
+    }
+
+    /// Recompiles the effects whose sources changed.
+    pub unsafe fn reload_if_changed(&mut self) {
+        for pass in &mut self.passes {
+            pass.shader.reload_if_changed();
+        }
+    }
+
+    /// Redirects the scene rendering to the first target, if there are effects.
+    pub unsafe fn begin(&self) {
+        if let Some(target) = self.targets.first() {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, target.fbo);
+        }
+    }
+
+    /// Runs every pass in order, the last one drawing to the screen.
+    pub unsafe fn finish(&self, time: f32) {
+        if self.passes.is_empty() {
+            return;
+        }
+
+        gl::Disable(gl::DEPTH_TEST);
+        gl::BindVertexArray(self.vao);
+        gl::ActiveTexture(gl::TEXTURE0 + SCREEN_UNIT);
+
+        let last = self.passes.len() - 1;
+        for (n, pass) in self.passes.iter().enumerate() {
+            let source = &self.targets[n % 2];
+            let target = if n == last {
+                0
+            } else {
+                self.targets[(n + 1) % 2].fbo
+            };
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, target);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+            gl::BindTexture(gl::TEXTURE_2D, source.color);
+
+            pass.shader.use_program();
+            pass.shader.set_int(c_str!("screen"), SCREEN_UNIT as i32);
+            // not every effect cares about these
+            if pass.shader.has_uniform(c_str!("resolution")) {
+                pass.shader.set_vec2(c_str!("resolution"),
+                                     vec2(self.width as f32, self.height as f32));
+            }
+            if pass.shader.has_uniform(c_str!("time")) {
+                pass.shader.set_float(c_str!("time"), time);
+            }
+            for &(ref name, value) in &pass.params {
+                pass.shader.set_float(name, value);
+            }
+
+            gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, ptr::null());
+        }
+
+        gl::Enable(gl::DEPTH_TEST);
+    }
+}
@@ -0,0 +1,228 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+
+/// Textures that can be generated instead of loaded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pattern {
+    Brick,
+    Tiles,
+    Stucco,
+    Checker,
+    Smiley,
+    Logo
+}
+
+/// What every pattern is generated from, `color` is the main
+/// one and `accent` is used for mortar, grout, speckles...
+#[derive(Debug, Clone, Copy)]
+pub struct Params {
+    pub size: u32,
+    pub seed: u32,
+    pub color: [u8; 3],
+    pub accent: [u8; 3]
+}
+
+impl Pattern {
+    pub fn parse(name: &str) -> Option<Pattern> {
+        match name {
+            "brick" => Some(Pattern::Brick),
+            "tiles" => Some(Pattern::Tiles),
+            "stucco" => Some(Pattern::Stucco),
+            "checker" => Some(Pattern::Checker),
+            "smiley" => Some(Pattern::Smiley),
+            "logo" => Some(Pattern::Logo),
+            _ => None
+        }
+    }
+
+    /// Reasonable colors for each pattern.
+    pub fn default_params(&self) -> Params {
+        let (color, accent) = match *self {
+            Pattern::Brick => ([150, 60, 40], [190, 180, 160]),
+            Pattern::Tiles => ([120, 120, 125], [60, 60, 60]),
+            Pattern::Stucco => ([200, 195, 180], [150, 145, 130]),
+            // the classic missing texture look
+            Pattern::Checker => ([255, 0, 255], [0, 0, 0]),
+            Pattern::Smiley => ([255, 220, 0], [0, 0, 0]),
+            // white plate, blue letters
+            Pattern::Logo => ([240, 240, 240], [20, 60, 160])
+        };
+
+        Params {
+            size: 64,
+            seed: 1,
+            color: color,
+            accent: accent
+        }
+    }
+
+    /// Every pattern tiles seamlessly, except for the
+    /// smiley and the logo which have transparent borders.
+    pub fn generate(&self, params: &Params) -> DynamicImage {
+        let size = params.size;
+        let img = RgbaImage::from_fn(size, size, |x, y| {
+            match *self {
+                Pattern::Brick => brick(params, x, y),
+                Pattern::Tiles => tiles(params, x, y),
+                Pattern::Stucco => stucco(params, x, y),
+                Pattern::Checker => checker(params, x, y),
+                Pattern::Smiley => smiley(params, x, y),
+                Pattern::Logo => logo(params, x, y)
+            }
+        });
+        DynamicImage::ImageRgba8(img)
+    }
+}
+
+// four rows of two bricks, every other row shifted by half a brick
+fn brick(params: &Params, x: u32, y: u32) -> Rgba<u8> {
+    let size = params.size;
+    let (brick_w, brick_h) = (size / 2, size / 4);
+    let mortar = (size / 32).max(1);
+
+    let row = y / brick_h;
+    let shifted = (x + (row % 2) * brick_w / 2) % size;
+    let col = shifted / brick_w;
+
+    if y % brick_h < mortar || shifted % brick_w < mortar {
+        let grain = hash(x, y, params.seed) * 0.2;
+        return mix(params.accent, [0, 0, 0], grain);
+    }
+
+    // each brick slightly different, plus some grain
+    let tone = hash(col, row, params.seed) * 0.3;
+    let grain = hash(x, y, params.seed.wrapping_add(1)) * 0.15;
+    mix(params.color, [0, 0, 0], tone + grain)
+}
+
+// 2x2 square tiles with grout lines
+fn tiles(params: &Params, x: u32, y: u32) -> Rgba<u8> {
+    let tile = params.size / 2;
+    let grout = (params.size / 32).max(1);
+
+    if x % tile < grout || y % tile < grout {
+        return mix(params.accent, [0, 0, 0], 0.0);
+    }
+
+    let tone = hash(x / tile, y / tile, params.seed) * 0.15;
+    let grain = hash(x, y, params.seed.wrapping_add(1)) * 0.08;
+    mix(params.color, [255, 255, 255], tone + grain)
+}
+
+// smooth blotches of both colors with a fine grain on top
+fn stucco(params: &Params, x: u32, y: u32) -> Rgba<u8> {
+    let coarse = value_noise(params, x, y, 8);
+    let fine = value_noise(params, x, y, 32);
+    let grain = hash(x, y, params.seed.wrapping_add(2));
+
+    let t = 0.5 * coarse + 0.3 * fine + 0.2 * grain;
+    mix(params.color, params.accent, t)
+}
+
+// 8x8 squares
+fn checker(params: &Params, x: u32, y: u32) -> Rgba<u8> {
+    let square = (params.size / 8).max(1);
+    if (x / square + y / square) % 2 == 0 {
+        mix(params.color, params.accent, 0.0)
+    } else {
+        mix(params.accent, params.color, 0.0)
+    }
+}
+
+// a round face in the middle, eyes and mouth in the accent color
+fn smiley(params: &Params, x: u32, y: u32) -> Rgba<u8> {
+    let size = params.size as f32;
+    // pixel centers, relative to the image
+    let (u, v) = ((x as f32 + 0.5) / size, (y as f32 + 0.5) / size);
+    let dist = |cx: f32, cy: f32| ((u - cx).powi(2) + (v - cy).powi(2)).sqrt();
+
+    let radius = 0.3;
+    let center = dist(0.5, 0.5);
+    if center > radius {
+        return TRANSPARENT;
+    }
+
+    let outline = center > radius - 0.03;
+    let eye = dist(0.4, 0.42) < 0.04 || dist(0.6, 0.42) < 0.04;
+    let mouth = v > 0.52 && center > 0.15 && center < 0.2;
+
+    if outline || eye || mouth {
+        mix(params.accent, [0, 0, 0], 0.0)
+    } else {
+        mix(params.color, [0, 0, 0], 0.0)
+    }
+}
+
+// "OpenGL" written with a tiny bitmap font on a plate
+fn logo(params: &Params, x: u32, y: u32) -> Rgba<u8> {
+    let size = params.size as f32;
+    let columns = (LOGO.len() * 6 - 1) as f32;
+    let scale = size * 0.8 / columns;
+    let (x0, y0) = ((size - columns * scale) / 2.0, (size - 7.0 * scale) / 2.0);
+
+    // position in font pixels, the plate has a border of two
+    let fx = ((x as f32 - x0) / scale).floor();
+    let fy = ((y as f32 - y0) / scale).floor();
+    if fx < -2.0 || fx >= columns + 2.0 || fy < -2.0 || fy >= 9.0 {
+        return TRANSPARENT;
+    }
+
+    let outline = fx < -1.0 || fx >= columns + 1.0 || fy < -1.0 || fy >= 8.0;
+    let letter = fx >= 0.0 && fx < columns && fy >= 0.0 && fy < 7.0 && {
+        let (col, row) = (fx as usize, fy as usize);
+        let glyph = LOGO[col / 6];
+        col % 6 < 5 && glyph[row] & (0b10000 >> (col % 6)) != 0
+    };
+
+    if outline || letter {
+        mix(params.accent, [0, 0, 0], 0.0)
+    } else {
+        mix(params.color, [0, 0, 0], 0.0)
+    }
+}
+
+// 5x7 glyphs, one row per byte
+const LOGO: [[u8; 7]; 6] = [
+    [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110], // O
+    [0b00000, 0b00000, 0b11110, 0b10001, 0b11110, 0b10000, 0b10000], // p
+    [0b00000, 0b00000, 0b01110, 0b10001, 0b11111, 0b10000, 0b01110], // e
+    [0b00000, 0b00000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001], // n
+    [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111], // G
+    [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111]  // L
+];
+
+const TRANSPARENT: Rgba<u8> = Rgba { data: [0, 0, 0, 0] };
+
+// bilinearly interpolated random lattice of `cells` per side,
+// wrapping around so the texture tiles
+fn value_noise(params: &Params, x: u32, y: u32, cells: u32) -> f32 {
+    let cell = params.size as f32 / cells as f32;
+    let (fx, fy) = (x as f32 / cell, y as f32 / cell);
+    let (x0, y0) = (fx as u32, fy as u32);
+    let (x1, y1) = ((x0 + 1) % cells, (y0 + 1) % cells);
+    let (tx, ty) = (fx - x0 as f32, fy - y0 as f32);
+
+    let seed = params.seed.wrapping_add(cells);
+    let top = lerp(hash(x0, y0, seed), hash(x1, y0, seed), tx);
+    let bottom = lerp(hash(x0, y1, seed), hash(x1, y1, seed), tx);
+    lerp(top, bottom, ty)
+}
+
+// a cheap integer hash mapped to [0, 1)
+fn hash(x: u32, y: u32, seed: u32) -> f32 {
+    let mut h = x.wrapping_mul(374761393)
+        .wrapping_add(y.wrapping_mul(668265263))
+        .wrapping_add(seed.wrapping_mul(2654435761));
+    h = (h ^ (h >> 13)).wrapping_mul(1274126177);
+    h ^= h >> 16;
+    (h & 0xffffff) as f32 / 0x1000000 as f32
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn mix(a: [u8; 3], b: [u8; 3], t: f32) -> Rgba<u8> {
+    let t = t.max(0.0).min(1.0);
+    let c = |i: usize| lerp(a[i] as f32, b[i] as f32, t) as u8;
+    Rgba { data: [c(0), c(1), c(2), 255] }
+}
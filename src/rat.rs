@@ -1,54 +1,121 @@
-use std::mem;
-use std::ptr;
 use std::ffi::CStr;
-use std::collections::HashMap;
+use std::f32::consts::FRAC_1_SQRT_2;
 
-use gl;
-use gl::types::*;
+use rand;
 use cgmath::{Matrix4, Vector3, EuclideanSpace, InnerSpace, MetricSpace};
 
-use texture::{Texture, TexType};
+use texture::{self, TextureArray, TexType};
 use shader::Shader;
+use billboard::Billboard;
 use walker::Walker;
+use camera::Camera;
+use behaviour::{Behaviour, Surroundings};
 
-// texture coordinates are weird because somehow
-// images are flipped on the x axis
-const VERTICES: [f32; 20] = [
-     0.5, -0.5, 0.0,  0.0, 1.0, // bottom right
-    -0.5, -0.5, 0.0,  1.0, 1.0, // bottom left
-    -0.5,  0.5, 0.0,  1.0, 0.0, // top left
-     0.5,  0.5, 0.0,  0.0, 0.0, // top right
-];
-const INDICES: [u32; 6] = [
-    0, 1, 3,
-    1, 2, 3
-];
 const MOVE_SPEED: f32 = 3.0;
+// distance walked between animation frames
+const STRIDE: f32 = 0.2;
+// sprite sheet rows, when there are three
+const FRONT: u32 = 0;
+const SIDE: u32 = 1;
+const BACK: u32 = 2;
+
+/// What the camera does when a rat is in the next cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reaction {
+    /// Walk right through it, like the original.
+    Pass,
+    /// Wait for it to leave, for a while.
+    Pause,
+    /// Turn around and go somewhere else.
+    Turn,
+    /// Look around startled, then walk through.
+    Look
+}
 
 pub struct Rat<'a> {
     pub pos: Vector3<f32>,
-    pub walker: Walker<'a>
+    pub walker: Walker<'a>,
+    // how far it has walked, drives the animation
+    pub distance: f32,
+    pub behaviour: Behaviour
 }
 
 #[derive(Debug)]
 pub struct RatRenderer {
-    vao: GLuint
+    quad: Billboard
+}
+
+impl Reaction {
+    pub fn parse(name: &str) -> Option<Reaction> {
+        match name {
+            "pass" => Some(Reaction::Pass),
+            "pause" => Some(Reaction::Pause),
+            "turn" => Some(Reaction::Turn),
+            "look" => Some(Reaction::Look),
+            _ => None
+        }
+    }
 }
 
 impl<'a> Rat<'a> {
-    pub fn update(&mut self, dt: f32) {
+    /// The cell it's in right now, not the one it's heading to.
+    pub fn cell(&self) -> (usize, usize) {
+        (self.pos.z as usize, self.pos.x as usize)
+    }
+
+    pub fn update(&mut self, dt: f32, surroundings: &Surroundings) {
         let p_to = self.walker.to_point().to_vec();
         let old_dir = (p_to - self.pos).normalize();
 
         self.pos += MOVE_SPEED * dt * old_dir;
+        self.distance += MOVE_SPEED * dt;
 
         // if new_dir is opposite direction from old_dir
         // then we went through, just assign it
-        // and update the walker
+        // and decide where to go next
         let new_dir = (p_to - self.pos).normalize();
         if old_dir.distance(new_dir) >= 0.5 {
             self.pos = p_to;
-            self.walker.next();
+            self.choose_next(surroundings);
+        }
+    }
+
+    fn choose_next(&mut self, surroundings: &Surroundings) {
+        let mut rng = rand::thread_rng();
+        let (i, j) = self.walker.pos();
+
+        self.behaviour = self.behaviour.next(surroundings.distances[i][j], &mut rng);
+
+        let options: Vec<_> = self.walker.options()
+            .into_iter()
+            .map(|d| (d, self.walker.ahead(d)))
+            .collect();
+        let direction = self.behaviour.choose(self.walker.direction, &options,
+                                              surroundings, &mut rng);
+        self.walker.step(direction);
+    }
+
+    /// Frame, view and mirroring of a sprite sheet with `frames`
+    /// columns and `views` rows, as seen from the camera.
+    pub fn sprite(&self, camera: &Camera, frames: u32, views: u32) -> (u32, u32, bool) {
+        let frame = (self.distance / STRIDE) as u32 % frames;
+        if views < 3 {
+            return (frame, 0, false);
+        }
+
+        let heading = self.walker.direction.to_vec();
+        let to_rat = (self.pos - camera.pos.to_vec()).normalize();
+        let along = heading.dot(to_rat);
+
+        // the quad shows images mirrored, mirroring it again shows them
+        // as drawn, so side views in the sheet should face right
+        if along > FRAC_1_SQRT_2 {
+            (frame, BACK, true)
+        } else if along < -FRAC_1_SQRT_2 {
+            (frame, FRONT, true)
+        } else {
+            let right = camera.dir.cross(camera.up);
+            (frame, SIDE, heading.dot(right) > 0.0)
         }
     }
 }
@@ -56,66 +123,30 @@ impl<'a> Rat<'a> {
 impl RatRenderer {
 
     pub unsafe fn new() -> RatRenderer {
-
-        let (mut vao, mut vbo, mut ebo) = (0, 0, 0);
-
-        //* generate VAO, VBO, EBO
-        gl::GenVertexArrays(1, &mut vao);
-        gl::GenBuffers(1, &mut vbo);
-        gl::GenBuffers(1, &mut ebo);
-
-        //* binding VAO
-        gl::BindVertexArray(vao);
-
-        //* VBO data
-        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-        gl::BufferData(gl::ARRAY_BUFFER,
-                       mem::size_of::<[f32; 20]>() as isize,
-                       VERTICES.as_ptr() as *const _,
-                       gl::STATIC_DRAW);
-
-        //* EBO data
-        gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
-        gl::BufferData(gl::ELEMENT_ARRAY_BUFFER,
-                       mem::size_of::<[u32; 6]>() as isize,
-                       INDICES.as_ptr() as *const _,
-                       gl::STATIC_DRAW);
-
-        //* vertex attribs
-        // aPos = 0
-        gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE,
-                                5 * mem::size_of::<GLfloat>() as GLint,
-                                ptr::null());
-        gl::EnableVertexAttribArray(0);
-        // aTex = 1
-        gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE,
-                                5 * mem::size_of::<GLfloat>() as GLint,
-                                (3 * mem::size_of::<GLfloat>()) as *const GLvoid);
-        gl::EnableVertexAttribArray(1);
-
         RatRenderer {
-            vao: vao
+            quad: Billboard::new()
         }
     }
 
     pub unsafe fn set_up(&self,
                          shader_program: &Shader,
-                         textures: &HashMap<TexType, Texture>) {
-        gl::BindVertexArray(self.vao);
-
-        shader_program.set_bool(c_str!("rat"), true);
-        shader_program.set_bool(c_str!("shaded"), false);
-
-        let rat_tex = textures[&TexType::Rat].number as i32;
-        shader_program.set_int(c_str!("tex"), rat_tex);
-        shader_program.set_int(c_str!("tiling"), TexType::Rat.tiling());
+                         textures: &TextureArray) {
+        self.quad.set_up(shader_program, textures, TexType::Sprite);
     }
 
-    pub unsafe fn draw(&self, shader_program: &Shader, rat: &Rat) {
+    pub unsafe fn draw(&self,
+                       shader_program: &Shader,
+                       textures: &TextureArray,
+                       rat: &Rat,
+                       camera: &Camera) {
         let model = Matrix4::from_translation(rat.pos);
 
         shader_program.set_mat4(c_str!("model"), model);
 
-        gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, ptr::null());
+        let layer = textures.layer(TexType::Sprite);
+        let (frame, view, mirror) = rat.sprite(camera, layer.frames, layer.views);
+        texture::set_frame(shader_program, frame, view, mirror);
+
+        self.quad.draw();
     }
 }
@@ -1,63 +1,212 @@
+use std::cmp;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::ffi::{CStr, CString};
-use std::fs::File;
-use std::io::Read;
+use std::marker::PhantomData;
+use std::fs;
 use std::ptr;
-use std::str;
+use std::time::SystemTime;
 
-use cgmath::{Matrix4, Vector3};
+use cgmath::{Matrix4, Vector2, Vector3};
 use cgmath::prelude::*;
 use gl;
 use gl::types::*;
 
+use error::Error;
+use assets::Assets;
+
 pub struct Shader {
     pub id: u32,
+    assets: Assets,
+    vertex_path: String,
+    fragment_path: String,
+    modified: Option<SystemTime>,
+    // locations of every active uniform, queried after linking
+    uniforms: HashMap<CString, GLint>,
+    // names already reported as missing, so we only warn once
+    missing: RefCell<HashSet<CString>>
+}
+
+/// A uniform location that knows the type of its value.
+/// Only valid until the program is reloaded.
+pub struct Uniform<T> {
+    location: GLint,
+    kind: PhantomData<T>
+}
+
+impl<T> Clone for Uniform<T> {
+    fn clone(&self) -> Uniform<T> {
+        Uniform {
+            location: self.location,
+            kind: PhantomData
+        }
+    }
+}
+
+impl<T> Copy for Uniform<T> {}
+
+/// Types that can be uploaded to a uniform.
+pub trait UniformValue {
+    unsafe fn upload(location: GLint, value: Self);
+}
+
+impl UniformValue for i32 {
+    unsafe fn upload(location: GLint, value: i32) {
+        gl::Uniform1i(location, value);
+    }
+}
+
+impl UniformValue for bool {
+    unsafe fn upload(location: GLint, value: bool) {
+        gl::Uniform1i(location, value as i32);
+    }
+}
+
+impl UniformValue for f32 {
+    unsafe fn upload(location: GLint, value: f32) {
+        gl::Uniform1f(location, value);
+    }
+}
+
+impl UniformValue for Vector2<f32> {
+    unsafe fn upload(location: GLint, value: Vector2<f32>) {
+        gl::Uniform2f(location, value.x, value.y);
+    }
+}
+
+impl UniformValue for Vector3<f32> {
+    unsafe fn upload(location: GLint, value: Vector3<f32>) {
+        gl::Uniform3f(location, value.x, value.y, value.z);
+    }
+}
+
+impl UniformValue for Matrix4<f32> {
+    unsafe fn upload(location: GLint, value: Matrix4<f32>) {
+        gl::UniformMatrix4fv(location, 1, gl::FALSE, value.as_ptr());
+    }
 }
 
 impl Shader {
-    pub fn new(vertex_path: &str, fragment_path: &str) -> Shader {
-        let mut shader = Shader { id: 0 };
-        // 1. retrieve the vertex/fragment source code from filesystem
-        let mut v_shader_file =
-            File::open(vertex_path).expect(&format!("Failed to open {}", vertex_path));
-        let mut f_shader_file =
-            File::open(fragment_path).expect(&format!("Failed to open {}", fragment_path));
-        let mut vertex_code = String::new();
-        let mut fragment_code = String::new();
-        v_shader_file
-            .read_to_string(&mut vertex_code)
-            .expect("Failed to read vertex shader");
-        f_shader_file
-            .read_to_string(&mut fragment_code)
-            .expect("Failed to read fragment shader");
-
-        let v_shader_code = CString::new(vertex_code.as_bytes()).unwrap();
-        let f_shader_code = CString::new(fragment_code.as_bytes()).unwrap();
-
-        // 2. compile shaders
+    pub fn new(assets: &Assets,
+               vertex_path: &str,
+               fragment_path: &str) -> Result<Shader, Error> {
+        let mut shader = Shader {
+            id: 0,
+            assets: assets.clone(),
+            vertex_path: vertex_path.to_string(),
+            fragment_path: fragment_path.to_string(),
+            modified: None,
+            uniforms: HashMap::new(),
+            missing: RefCell::new(HashSet::new())
+        };
+        shader.modified = shader.last_modified();
         unsafe {
-            // vertex shader
-            let vertex = gl::CreateShader(gl::VERTEX_SHADER);
-            gl::ShaderSource(vertex, 1, &v_shader_code.as_ptr(), ptr::null());
-            gl::CompileShader(vertex);
-            shader.check_compile_errors(vertex, "VERTEX");
-            // fragment Shader
-            let fragment = gl::CreateShader(gl::FRAGMENT_SHADER);
-            gl::ShaderSource(fragment, 1, &f_shader_code.as_ptr(), ptr::null());
-            gl::CompileShader(fragment);
-            shader.check_compile_errors(fragment, "FRAGMENT");
-            // shader Program
-            let id = gl::CreateProgram();
-            gl::AttachShader(id, vertex);
-            gl::AttachShader(id, fragment);
-            gl::LinkProgram(id);
-            shader.check_compile_errors(id, "PROGRAM");
-            // delete the shaders as they're linked into our program now and no longer necessary
-            gl::DeleteShader(vertex);
-            gl::DeleteShader(fragment);
-            shader.id = id;
+            shader.id = shader.compile()?;
+            shader.query_uniforms();
         }
+        Ok(shader)
+    }
+
+    /// recompile if any of the source files changed since the last time,
+    /// keeping the current program if the new one doesn't compile.
+    /// returns `true` if the program was replaced.
 
-        shader
+    pub unsafe fn reload_if_changed(&mut self) -> bool {
+        let modified = self.last_modified();
+        if modified <= self.modified {
+            return false;
+        }
+        self.modified = modified;
+
+        match self.compile() {
+            Ok(id) => {
+                gl::DeleteProgram(self.id);
+                self.id = id;
+                self.query_uniforms();
+                println!("Reloaded {} and {}", self.vertex_path, self.fragment_path);
+                true
+            }
+            Err(err) => {
+                println!("{}\nKeeping the previous program.", err);
+                false
+            }
+        }
+    }
+
+    /// only files overridden in the assets directory can change,
+    /// the embedded ones have no modification time.
+
+    fn last_modified(&self) -> Option<SystemTime> {
+        let mtime = |name: &str| {
+            self.assets.path(name)
+                .and_then(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+        };
+        cmp::max(mtime(&self.vertex_path), mtime(&self.fragment_path))
+    }
+
+    unsafe fn compile(&self) -> Result<u32, Error> {
+        // 1. compile both stages
+        let vertex = self.compile_stage(&self.vertex_path, gl::VERTEX_SHADER, "VERTEX")?;
+        let fragment = match self.compile_stage(&self.fragment_path,
+                                                gl::FRAGMENT_SHADER,
+                                                "FRAGMENT") {
+            Ok(fragment) => fragment,
+            Err(err) => {
+                gl::DeleteShader(vertex);
+                return Err(err);
+            }
+        };
+
+        // 2. shader Program
+        let id = gl::CreateProgram();
+        gl::AttachShader(id, vertex);
+        gl::AttachShader(id, fragment);
+        gl::LinkProgram(id);
+        let result = self.check_compile_errors(id, "PROGRAM");
+        // delete the shaders as they're linked into our program now and no longer necessary
+        gl::DeleteShader(vertex);
+        gl::DeleteShader(fragment);
+
+        match result {
+            Ok(()) => Ok(id),
+            Err(log) => {
+                gl::DeleteProgram(id);
+                Err(Error::Link {
+                    vertex_path: self.vertex_path.clone(),
+                    fragment_path: self.fragment_path.clone(),
+                    log: log
+                })
+            }
+        }
+    }
+
+    unsafe fn compile_stage(&self,
+                            path: &str,
+                            kind: GLenum,
+                            stage: &'static str) -> Result<u32, Error> {
+        // retrieve the source code from the assets
+        let code = self.assets.read_to_string(path)?;
+        let code = CString::new(code.into_bytes()).map_err(|_| Error::Compile {
+            path: path.to_string(),
+            stage: stage,
+            log: "source contains a nul byte".to_string()
+        })?;
+
+        let shader = gl::CreateShader(kind);
+        gl::ShaderSource(shader, 1, &code.as_ptr(), ptr::null());
+        gl::CompileShader(shader);
+
+        match self.check_compile_errors(shader, stage) {
+            Ok(()) => Ok(shader),
+            Err(log) => {
+                gl::DeleteShader(shader);
+                Err(Error::Compile {
+                    path: path.to_string(),
+                    stage: stage,
+                    log: log
+                })
+            }
+        }
     }
 
     /// activate the shader
@@ -68,37 +217,96 @@ impl Shader {
 
     /// utility uniform functions
 
+    pub fn has_uniform(&self, name: &CStr) -> bool {
+        self.uniforms.contains_key(name)
+    }
+
+    /// look up a uniform in the cache, warning once if the program
+    /// doesn't declare it (or the compiler optimized it away).
+    pub fn uniform<T: UniformValue>(&self, name: &CStr) -> Uniform<T> {
+        let location = match self.uniforms.get(name) {
+            Some(&location) => location,
+            None => {
+                if self.missing.borrow_mut().insert(name.to_owned()) {
+                    println!("Uniform {:?} is not used by {} + {}",
+                             name, self.vertex_path, self.fragment_path);
+                }
+                // setting location -1 is silently ignored by GL
+                -1
+            }
+        };
+
+        Uniform {
+            location: location,
+            kind: PhantomData
+        }
+    }
+
+    pub unsafe fn set<T: UniformValue>(&self, uniform: Uniform<T>, value: T) {
+        T::upload(uniform.location, value);
+    }
+
     pub unsafe fn set_int(&self, name: &CStr, value: i32) {
-        gl::Uniform1i(gl::GetUniformLocation(self.id, name.as_ptr()), value);
+        self.set(self.uniform(name), value);
     }
 
     pub unsafe fn set_bool(&self, name: &CStr, value: bool) {
-        gl::Uniform1i(gl::GetUniformLocation(self.id, name.as_ptr()), value as i32);
+        self.set(self.uniform(name), value);
     }
 
-    //pub unsafe fn set_float(&self, name: &CStr, value: f32) {
-    //    gl::Uniform1f(gl::GetUniformLocation(self.id, name.as_ptr()), value);
-    //}
+    pub unsafe fn set_float(&self, name: &CStr, value: f32) {
+        self.set(self.uniform(name), value);
+    }
+
+    pub unsafe fn set_vec2(&self, name: &CStr, value: Vector2<f32>) {
+        self.set(self.uniform(name), value);
+    }
 
     pub unsafe fn set_vec3(&self, name: &CStr, value: Vector3<f32>) {
-        gl::Uniform3f(
-            gl::GetUniformLocation(self.id, name.as_ptr()),
-            value.x,
-            value.y,
-            value.z);
+        self.set(self.uniform(name), value);
     }
 
     pub unsafe fn set_mat4(&self, name: &CStr, value: Matrix4<f32>) {
-        gl::UniformMatrix4fv(
-            gl::GetUniformLocation(self.id, name.as_ptr()),
-            1,
-            gl::FALSE,
-            value.as_ptr());
+        self.set(self.uniform(name), value);
+    }
+
+    /// fill the location cache with every active uniform of the program.
+
+    unsafe fn query_uniforms(&mut self) {
+        self.uniforms.clear();
+        self.missing.borrow_mut().clear();
+
+        let mut count = 0;
+        gl::GetProgramiv(self.id, gl::ACTIVE_UNIFORMS, &mut count);
+
+        let mut name = vec![0u8; 256];
+        for index in 0..count as GLuint {
+            let (mut length, mut size, mut type_) = (0, 0, 0);
+            gl::GetActiveUniform(self.id,
+                                 index,
+                                 name.len() as GLsizei,
+                                 &mut length,
+                                 &mut size,
+                                 &mut type_,
+                                 name.as_mut_ptr() as *mut GLchar);
+
+            let mut bytes = name[..length as usize].to_vec();
+            // arrays are reported as `name[0]`, but set by `name`
+            if bytes.ends_with(b"[0]") {
+                let len = bytes.len() - 3;
+                bytes.truncate(len);
+            }
+
+            let key = CString::new(bytes).unwrap();
+            let location = gl::GetUniformLocation(self.id, key.as_ptr());
+            self.uniforms.insert(key, location);
+        }
     }
 
     /// utility function for checking shader compilation/linking errors.
+    /// returns the driver log if there were any.
 
-    unsafe fn check_compile_errors(&self, shader: u32, type_: &str) {
+    unsafe fn check_compile_errors(&self, shader: u32, type_: &str) -> Result<(), String> {
         let mut success = gl::FALSE as GLint;
         let mut info_log = Vec::with_capacity(1024);
         let mut length = 0;
@@ -113,13 +321,6 @@ impl Shader {
                     &mut length,
                     info_log.as_mut_ptr() as *mut GLchar,
                 );
-                info_log.truncate(length as usize);
-                println!(
-                    "ERROR::SHADER_COMPILATION_ERROR of type: {}\n{}\n \
-                     ---------------------------------------------------",
-                    type_,
-                    str::from_utf8(&info_log).unwrap()
-                );
             }
         } else {
             gl::GetProgramiv(shader, gl::LINK_STATUS, &mut success);
@@ -130,14 +331,14 @@ impl Shader {
                     &mut length,
                     info_log.as_mut_ptr() as *mut GLchar,
                 );
-                info_log.truncate(length as usize);
-                println!(
-                    "ERROR::PROGRAM_LINKING_ERROR of type: {}\n{}\n \
-                     -- --------------------------------------------------- -- ",
-                    type_,
-                    str::from_utf8(&info_log).unwrap()
-                );
             }
         }
+
+        if success == gl::TRUE as GLint {
+            Ok(())
+        } else {
+            info_log.truncate(length as usize);
+            Err(String::from_utf8_lossy(&info_log).into_owned())
+        }
     }
 }
@@ -0,0 +1,60 @@
+use std::ffi::CStr;
+
+use cgmath::{Matrix4, Vector3, vec3};
+
+use texture::{TextureArray, TexType};
+use shader::Shader;
+use billboard::Billboard;
+
+/// A smiley face floating in a cell, walking into it makes a new maze.
+#[derive(Debug)]
+pub struct Smiley {
+    pub pos: Vector3<f32>,
+    pub i: usize,
+    pub j: usize
+}
+
+#[derive(Debug)]
+pub struct SmileyRenderer {
+    quad: Billboard
+}
+
+impl Smiley {
+    pub fn new(i: usize, j: usize) -> Smiley {
+        Smiley {
+            pos: vec3(j as f32 + 0.5, 0.0, i as f32 + 0.5),
+            i: i,
+            j: j
+        }
+    }
+
+    pub fn pos(&self) -> (usize, usize) {
+        (self.i, self.j)
+    }
+}
+
+impl SmileyRenderer {
+
+    pub unsafe fn new() -> SmileyRenderer {
+        SmileyRenderer {
+            quad: Billboard::new()
+        }
+    }
+
+    /// The smiley is a billboard just like the rats.
+    pub unsafe fn set_up(&self,
+                         shader_program: &Shader,
+                         textures: &TextureArray) {
+        self.quad.set_up(shader_program, textures, TexType::Smiley);
+    }
+
+    pub unsafe fn draw(&self, shader_program: &Shader, smiley: &Smiley, t: f32) {
+        // bobbing up and down
+        let bob = vec3(0.0, 0.05 * (t * 3.0).sin(), 0.0);
+        let model = Matrix4::from_translation(smiley.pos + bob);
+
+        shader_program.set_mat4(c_str!("model"), model);
+
+        self.quad.draw();
+    }
+}
@@ -0,0 +1,117 @@
+use std::f32::consts::PI;
+
+use cgmath::{vec3, Vector3, InnerSpace};
+use rand::{self, Rng};
+
+use mesh::Mesh;
+
+// the golden ratio, everywhere in dodecahedra and icosahedra
+const PHI: f32 = 1.618034;
+
+/// The five Platonic solids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Solid {
+    Tetrahedron,
+    Cube,
+    Octahedron,
+    Dodecahedron,
+    Icosahedron
+}
+
+impl Solid {
+    pub const ALL: [Solid; 5] = [
+        Solid::Tetrahedron,
+        Solid::Cube,
+        Solid::Octahedron,
+        Solid::Dodecahedron,
+        Solid::Icosahedron
+    ];
+
+    pub fn random() -> Solid {
+        *rand::thread_rng().choose(&Solid::ALL).unwrap()
+    }
+
+    /// Flat shaded mesh with its vertices on the unit sphere.
+    pub fn mesh(&self) -> Mesh {
+        let vertices = self.vertices();
+        // the faces of a solid point to the vertices of its dual,
+        // and the tetrahedron is its own (upside down) dual
+        let directions = match *self {
+            Solid::Tetrahedron => vertices.iter().map(|&v| -v).collect(),
+            Solid::Cube => Solid::Octahedron.vertices(),
+            Solid::Octahedron => Solid::Cube.vertices(),
+            Solid::Dodecahedron => Solid::Icosahedron.vertices(),
+            Solid::Icosahedron => Solid::Dodecahedron.vertices()
+        };
+
+        let faces: Vec<Vec<usize>> = directions.iter()
+            .map(|&dir| face(&vertices, dir))
+            .collect();
+
+        Mesh::from_faces(&vertices, &faces)
+    }
+
+    fn vertices(&self) -> Vec<Vector3<f32>> {
+        let mut vertices = Vec::new();
+        let signs = [1.0, -1.0];
+
+        match *self {
+            Solid::Tetrahedron => {
+                vertices.push(vec3(1.0, 1.0, 1.0));
+                vertices.push(vec3(1.0, -1.0, -1.0));
+                vertices.push(vec3(-1.0, 1.0, -1.0));
+                vertices.push(vec3(-1.0, -1.0, 1.0));
+            }
+            Solid::Cube => {
+                for &x in &signs { for &y in &signs { for &z in &signs {
+                    vertices.push(vec3(x, y, z));
+                }}}
+            }
+            Solid::Octahedron => {
+                for &s in &signs {
+                    vertices.push(vec3(s, 0.0, 0.0));
+                    vertices.push(vec3(0.0, s, 0.0));
+                    vertices.push(vec3(0.0, 0.0, s));
+                }
+            }
+            Solid::Dodecahedron => {
+                vertices = Solid::Cube.vertices();
+                for &a in &signs { for &b in &signs {
+                    vertices.push(vec3(0.0, a * PHI, b / PHI));
+                    vertices.push(vec3(a * PHI, b / PHI, 0.0));
+                    vertices.push(vec3(a / PHI, 0.0, b * PHI));
+                }}
+            }
+            Solid::Icosahedron => {
+                for &a in &signs { for &b in &signs {
+                    vertices.push(vec3(0.0, a, b * PHI));
+                    vertices.push(vec3(a, b * PHI, 0.0));
+                    vertices.push(vec3(a * PHI, 0.0, b));
+                }}
+            }
+        }
+
+        vertices.into_iter().map(|v| v.normalize()).collect()
+    }
+}
+
+// the vertices furthest along `dir`, sorted around it
+fn face(vertices: &[Vector3<f32>], dir: Vector3<f32>) -> Vec<usize> {
+    let furthest = vertices.iter().map(|v| v.dot(dir)).fold(f32::NEG_INFINITY, f32::max);
+    let mut corners: Vec<usize> = (0..vertices.len())
+        .filter(|&i| vertices[i].dot(dir) > furthest - 1e-4)
+        .collect();
+
+    let center = corners.iter().fold(vec3(0.0, 0.0, 0.0), |sum, &i| sum + vertices[i])
+        / corners.len() as f32;
+    let u = (vertices[corners[0]] - center).normalize();
+    let w = dir.cross(u);
+    let angle = |i: usize| {
+        let p = vertices[i] - center;
+        let a = p.dot(w).atan2(p.dot(u));
+        if a < 0.0 { a + 2.0 * PI } else { a }
+    };
+    corners.sort_by(|&a, &b| angle(a).partial_cmp(&angle(b)).unwrap());
+
+    corners
+}
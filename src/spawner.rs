@@ -0,0 +1,239 @@
+use std::cmp;
+use std::collections::HashSet;
+
+use rand::{self, Rng};
+
+use maze::Maze;
+use walker::Direction;
+
+/// How many entities the maze should have and how fast they come back,
+/// like `icos=6,rats=5,every=2,despawn=12`.
+#[derive(Debug, Clone)]
+pub struct Rates {
+    /// percent of the cells with a solid
+    pub icos: f32,
+    /// percent of the cells with a rat
+    pub rats: f32,
+    /// seconds between spawns
+    pub every: f64,
+    /// rats further than this many steps from the camera are removed
+    pub despawn: Option<usize>
+}
+
+/// Keeps the maze populated, placing new entities where the camera can't see.
+#[derive(Debug)]
+pub struct Spawner {
+    pub rates: Rates,
+    last: f64
+}
+
+impl Rates {
+    pub fn parse(spec: &str) -> Result<Rates, String> {
+        let mut rates = Rates::default();
+
+        for pair in spec.split(',').filter(|s| !s.is_empty()) {
+            let mut kv = pair.splitn(2, '=');
+            let key = kv.next().unwrap_or("");
+            let value = kv.next().ok_or_else(|| format!("expected `key=value`, got `{}`", pair))?;
+            let bad = || format!("bad {} `{}`", key, value);
+
+            match key {
+                "icos" => rates.icos = value.parse().ok().filter(|&p| p >= 0.0).ok_or_else(bad)?,
+                "rats" => rates.rats = value.parse().ok().filter(|&p| p >= 0.0).ok_or_else(bad)?,
+                "every" => rates.every = value.parse().ok().filter(|&s| s > 0.0).ok_or_else(bad)?,
+                "despawn" => rates.despawn = Some(value.parse().map_err(|_| bad())?),
+                _ => return Err(format!("unknown rate `{}`", key))
+            }
+        }
+
+        Ok(rates)
+    }
+
+    /// How many entities `percent` of the cells of `maze` are,
+    /// at least 2 so small mazes get some, unless it's 0.
+    pub fn count(percent: f32, maze: &Maze) -> usize {
+        let total = maze.width * maze.height;
+        if percent <= 0.0 {
+            return 0;
+        }
+        cmp::max((percent / 100.0 * total as f32) as usize, 2).min(total)
+    }
+}
+
+impl Default for Rates {
+    /// As many as the maze starts with, one every couple of seconds.
+    fn default() -> Rates {
+        Rates {
+            icos: 6.0,
+            rats: 5.0,
+            every: 2.0,
+            despawn: None
+        }
+    }
+}
+
+impl Spawner {
+    pub fn new(rates: Rates) -> Spawner {
+        Spawner {
+            rates: rates,
+            last: 0.0
+        }
+    }
+
+    /// Whether it's time to spawn, once every `rates.every` seconds.
+    pub fn due(&mut self, time: f64) -> bool {
+        if time - self.last >= self.rates.every {
+            self.last = time;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// A random cell the camera at `cell` looking `facing` can't see,
+    /// and that isn't `taken`.
+    pub fn hidden_cell<F>(&self,
+                          maze: &Maze,
+                          cell: (usize, usize),
+                          facing: Direction,
+                          taken: F) -> Option<(usize, usize)>
+        where F: Fn((usize, usize)) -> bool {
+        let visible = visible_cells(maze, cell, facing);
+
+        let mut candidates = Vec::new();
+        for i in 0..maze.height {
+            for j in 0..maze.width {
+                if !visible.contains(&(i, j)) && !taken((i, j)) {
+                    candidates.push((i, j));
+                }
+            }
+        }
+
+        rand::thread_rng().choose(&candidates).cloned()
+    }
+}
+
+// a conservative guess: the corridors in every direction from the camera,
+// the one straight ahead to its wall, and every side opening along it
+// walked to its own wall too
+fn visible_cells(maze: &Maze, cell: (usize, usize), facing: Direction) -> HashSet<(usize, usize)> {
+    let mut visible = HashSet::new();
+    visible.insert(cell);
+
+    for &d in &[Direction::North, Direction::East, Direction::South, Direction::West] {
+        visible.extend(corridor(maze, cell, d));
+    }
+
+    let ahead = corridor(maze, cell, facing);
+    for &(i, j) in Some(&cell).into_iter().chain(&ahead) {
+        for &side in &[facing.left(), facing.left().opposite()] {
+            visible.extend(corridor(maze, (i, j), side));
+        }
+    }
+
+    visible
+}
+
+// the cells from `cell` (not included) going `direction` until a wall
+fn corridor(maze: &Maze, cell: (usize, usize), direction: Direction) -> Vec<(usize, usize)> {
+    let mut cells = Vec::new();
+    let (mut i, mut j) = cell;
+    loop {
+        let wall = match direction {
+            Direction::North => maze.north(i, j),
+            Direction::East => maze.east(i, j),
+            Direction::South => maze.south(i, j),
+            Direction::West => maze.west(i, j)
+        };
+        if wall {
+            return cells;
+        }
+
+        match direction {
+            Direction::North => i -= 1,
+            Direction::East => j += 1,
+            Direction::South => i += 1,
+            Direction::West => j -= 1
+        }
+        cells.push((i, j));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maze::Maze;
+    use walker::Direction;
+    use super::{Rates, Spawner, visible_cells};
+
+    // a corridor along row 2 with two side corridors, one of them turning:
+    //             (0,2) (0,3) (0,4)
+    //             (1,2)
+    // (2,0) (2,1) (2,2) (2,3) (2,4)
+    //                   (3,3)
+    //                   (4,3)
+    fn corridors() -> Maze {
+        let mut maze = Maze::closed(5, 5);
+        for j in 0..4 {
+            maze.connect((2, j), (2, j + 1));
+        }
+        maze.connect((2, 2), (1, 2));
+        maze.connect((1, 2), (0, 2));
+        maze.connect((0, 2), (0, 3));
+        maze.connect((0, 3), (0, 4));
+        maze.connect((2, 3), (3, 3));
+        maze.connect((3, 3), (4, 3));
+        maze
+    }
+
+    // down the corridor from its west end, and down both side corridors
+    const SEEN: [(usize, usize); 9] = [(2, 0), (2, 1), (2, 2), (2, 3), (2, 4),
+                                       (1, 2), (0, 2), (3, 3), (4, 3)];
+
+    #[test]
+    fn corridors_and_their_side_openings_are_visible() {
+        let maze = corridors();
+        let visible = visible_cells(&maze, (2, 0), Direction::East);
+
+        for cell in SEEN.iter() {
+            assert!(visible.contains(cell), "{:?} should be visible", cell);
+        }
+        // around the corner of the north corridor
+        assert!(!visible.contains(&(0, 3)));
+        assert!(!visible.contains(&(0, 4)));
+    }
+
+    #[test]
+    fn hidden_cells_are_never_seen() {
+        let maze = corridors();
+        let spawner = Spawner::new(Rates::default());
+
+        for _ in 0..200 {
+            let cell = spawner.hidden_cell(&maze, (2, 0), Direction::East, |_| false).unwrap();
+            assert!(!SEEN.contains(&cell), "spawned at {:?}", cell);
+        }
+    }
+
+    #[test]
+    fn hidden_cells_are_not_taken() {
+        let maze = corridors();
+        let spawner = Spawner::new(Rates::default());
+
+        // everything but one hidden cell taken
+        for _ in 0..20 {
+            let cell = spawner.hidden_cell(&maze, (2, 0), Direction::East, |c| c != (4, 4));
+            assert_eq!(cell, Some((4, 4)));
+        }
+        assert_eq!(spawner.hidden_cell(&maze, (2, 0), Direction::East, |_| true), None);
+    }
+
+    #[test]
+    fn counts_are_at_least_two_unless_zero() {
+        // 25 cells
+        let maze = corridors();
+
+        assert_eq!(Rates::count(0.0, &maze), 0);
+        assert_eq!(Rates::count(1.0, &maze), 2);
+        assert_eq!(Rates::count(20.0, &maze), 5);
+        assert_eq!(Rates::count(500.0, &maze), 25);
+    }
+}
@@ -1,70 +1,320 @@
-use std::path::Path;
+use std::ptr;
+use std::cell::Cell;
+use std::ffi::CStr;
+use std::collections::HashMap;
 
 use gl;
 use gl::types::*;
-use image;
-use image::GenericImage;
+use image::{imageops, DynamicImage, FilterType, GenericImage};
+use cgmath::vec2;
 
+use shader::Shader;
+
+// from EXT_texture_filter_anisotropic, not in the core 3.3 bindings
+const TEXTURE_MAX_ANISOTROPY_EXT: GLenum = 0x84FE;
+const MAX_TEXTURE_MAX_ANISOTROPY_EXT: GLenum = 0x84FF;
+// no point in going higher
+const MAX_ANISOTROPY: f32 = 16.0;
+
+/// Every texture of the maze packed as layers of a single
+/// `GL_TEXTURE_2D_ARRAY`, so switching textures is just a uniform.
 #[derive(Debug)]
-pub struct Texture {
+pub struct TextureArray {
     pub id: GLuint,
-    pub number: u32
+    pub number: u32,
+    layers: HashMap<TexType, Layer>,
+    // the sampler currently bound to our unit
+    bound_sampler: Cell<GLuint>
+}
+
+/// Where a role's image is in the array and how to sample it.
+#[derive(Debug, Clone, Copy)]
+pub struct Layer {
+    pub index: i32,
+    pub tiling: i32,
+    pub frames: u32,
+    pub views: u32,
+    sampler: GLuint
+}
+
+/// Texture sampling, from blocky to smooth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Filter {
+    /// no filtering nor mipmaps, the retro look
+    Nearest,
+    /// smooth but without mipmaps, shimmers in the distance
+    Bilinear,
+    /// smooth with mipmaps
+    Trilinear,
+    /// trilinear plus anisotropic filtering where supported
+    Anisotropic
+}
+
+/// How an image is turned into a texture.
+#[derive(Debug, Clone, Copy)]
+pub struct TexSettings {
+    /// times the image repeats on each face
+    pub tiling: i32,
+    /// color made fully transparent, for images without alpha
+    pub chroma_key: Option<[u8; 3]>,
+    pub filter: Filter,
+    /// columns of a sprite sheet, one per animation frame
+    pub frames: u32,
+    /// rows of a sprite sheet: just one, or front, side and back
+    pub views: u32
 }
 
+/// The role a texture plays in the maze, themes map each to an image.
 #[derive(Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum TexType {
-    Rat,
-    Brick,
-    Thing,
+    Sprite,
+    Wall,
+    AltWall,
     Ceiling,
     Floor,
-    Other // dummy type for `last_textype`
+    Smiley,
+    Logo
 }
 
-impl Texture {
-    pub unsafe fn new(name: &str, number: u32) -> Texture {
-        let mut id = 0;
-
-        let img = image::open(&Path::new(name))
-            .expect("Failed to load texture.");
-        let data = img.raw_pixels();
+impl TextureArray {
+    /// Images of different sizes are scaled to the largest one.
+    pub unsafe fn new(images: &[(TexType, DynamicImage, TexSettings)],
+                      number: u32) -> TextureArray {
+        let width = images.iter().map(|&(_, ref img, _)| img.width()).max().unwrap_or(1);
+        let height = images.iter().map(|&(_, ref img, _)| img.height()).max().unwrap_or(1);
 
+        let mut id = 0;
         gl::GenTextures(1, &mut id);
-        gl::BindTexture(gl::TEXTURE_2D, id);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
-        gl::TexImage2D(gl::TEXTURE_2D,
+        gl::ActiveTexture(gl::TEXTURE0 + number);
+        gl::BindTexture(gl::TEXTURE_2D_ARRAY, id);
+        gl::TexImage3D(gl::TEXTURE_2D_ARRAY,
                        0,
-                       gl::RGB as i32,
-                       img.width() as i32,
-                       img.height() as i32,
+                       gl::RGBA as i32,
+                       width as i32,
+                       height as i32,
+                       images.len() as i32,
                        0,
-                       gl::RGB,
+                       gl::RGBA,
                        gl::UNSIGNED_BYTE,
-                       &data[0] as *const u8 as *const GLvoid);
-        gl::GenerateMipmap(gl::TEXTURE_2D);
+                       ptr::null());
+
+        let mut samplers = HashMap::new();
+        let mut layers = HashMap::new();
+
+        for (index, &(textype, ref img, ref settings)) in images.iter().enumerate() {
+            let mut rgba = img.to_rgba();
+            if let Some(key) = settings.chroma_key {
+                for pixel in rgba.pixels_mut() {
+                    if pixel.data[..3] == key {
+                        pixel.data[3] = 0;
+                    }
+                }
+            }
+            if rgba.dimensions() != (width, height) {
+                // nearest keeps both the pixelated look and the chroma keyed edges
+                rgba = imageops::resize(&rgba, width, height, FilterType::Nearest);
+            }
+            let data = rgba.into_raw();
+
+            gl::TexSubImage3D(gl::TEXTURE_2D_ARRAY,
+                              0,
+                              0,
+                              0,
+                              index as i32,
+                              width as i32,
+                              height as i32,
+                              1,
+                              gl::RGBA,
+                              gl::UNSIGNED_BYTE,
+                              &data[0] as *const u8 as *const GLvoid);
+
+            // one sampler per filter, shared between layers
+            let sampler = *samplers.entry(settings.filter)
+                .or_insert_with(|| settings.filter.sampler());
+
+            layers.insert(textype, Layer {
+                index: index as i32,
+                tiling: settings.tiling,
+                frames: settings.frames,
+                views: settings.views,
+                sampler: sampler
+            });
+        }
+
+        if samplers.keys().any(|filter| filter.uses_mipmaps()) {
+            gl::GenerateMipmap(gl::TEXTURE_2D_ARRAY);
+        }
 
-        Texture {
+        TextureArray {
             id: id,
-            number: number
+            number: number,
+            layers: layers,
+            bound_sampler: Cell::new(0)
         }
     }
 
     pub unsafe fn bind(&self) {
         gl::ActiveTexture(gl::TEXTURE0 + self.number);
-        gl::BindTexture(gl::TEXTURE_2D, self.id);
+        gl::BindTexture(gl::TEXTURE_2D_ARRAY, self.id);
+    }
+
+    pub fn layer(&self, textype: TexType) -> Layer {
+        self.layers[&textype]
+    }
+
+    /// Sets the uniforms to draw with the texture of `textype`,
+    /// only switching samplers when the filter changes.
+    pub unsafe fn select(&self, shader_program: &Shader, textype: TexType) {
+        let layer = self.layer(textype);
+
+        shader_program.set_int(c_str!("layer"), layer.index);
+        shader_program.set_int(c_str!("tiling"), layer.tiling);
+
+        if self.bound_sampler.get() != layer.sampler {
+            gl::BindSampler(self.number, layer.sampler);
+            self.bound_sampler.set(layer.sampler);
+        }
+    }
+
+    /// Like `select`, for a sprite sheet starting at the first frame
+    /// of the first view.
+    pub unsafe fn select_sprite(&self, shader_program: &Shader, textype: TexType) {
+        self.select(shader_program, textype);
+
+        let layer = self.layer(textype);
+        shader_program.set_vec2(c_str!("sheet"),
+                                vec2(layer.frames as f32, layer.views as f32));
+        set_frame(shader_program, 0, 0, false);
+    }
+}
+
+/// Goes back to drawing whole images after sprite sheets.
+pub unsafe fn whole_image(shader_program: &Shader) {
+    shader_program.set_vec2(c_str!("sheet"), vec2(1.0, 1.0));
+    set_frame(shader_program, 0, 0, false);
+}
+
+/// Which cell of the current sprite sheet to draw, optionally mirrored.
+pub unsafe fn set_frame(shader_program: &Shader, frame: u32, view: u32, mirror: bool) {
+    shader_program.set_vec2(c_str!("frame"), vec2(frame as f32, view as f32));
+    shader_program.set_bool(c_str!("mirror"), mirror);
+}
+
+impl Drop for TextureArray {
+    fn drop(&mut self) {
+        let mut samplers: Vec<GLuint> = self.layers.values().map(|l| l.sampler).collect();
+        samplers.sort();
+        samplers.dedup();
+
+        unsafe {
+            gl::DeleteSamplers(samplers.len() as GLsizei, samplers.as_ptr());
+            gl::DeleteTextures(1, &self.id);
+        }
+    }
+}
+
+impl Filter {
+    pub fn parse(name: &str) -> Option<Filter> {
+        match name {
+            "nearest" => Some(Filter::Nearest),
+            "bilinear" => Some(Filter::Bilinear),
+            "trilinear" => Some(Filter::Trilinear),
+            "anisotropic" => Some(Filter::Anisotropic),
+            _ => None
+        }
+    }
+
+    pub fn uses_mipmaps(&self) -> bool {
+        match *self {
+            Filter::Nearest |
+            Filter::Bilinear => false,
+            Filter::Trilinear |
+            Filter::Anisotropic => true
+        }
+    }
+
+    /// A sampler object with this filter.
+    pub unsafe fn sampler(&self) -> GLuint {
+        let (min, mag) = match *self {
+            Filter::Nearest => (gl::NEAREST, gl::NEAREST),
+            Filter::Bilinear => (gl::LINEAR, gl::LINEAR),
+            Filter::Trilinear |
+            Filter::Anisotropic => (gl::LINEAR_MIPMAP_LINEAR, gl::LINEAR)
+        };
+
+        let mut sampler = 0;
+        gl::GenSamplers(1, &mut sampler);
+        gl::SamplerParameteri(sampler, gl::TEXTURE_MIN_FILTER, min as i32);
+        gl::SamplerParameteri(sampler, gl::TEXTURE_MAG_FILTER, mag as i32);
+        gl::SamplerParameteri(sampler, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
+        gl::SamplerParameteri(sampler, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
+
+        if *self == Filter::Anisotropic {
+            match max_anisotropy() {
+                Some(max) => gl::SamplerParameterf(sampler, TEXTURE_MAX_ANISOTROPY_EXT, max),
+                None => println!("Anisotropic filtering not supported, using trilinear.")
+            }
+        }
+
+        sampler
+    }
+}
+
+// the highest anisotropy level, if the extension is there
+unsafe fn max_anisotropy() -> Option<f32> {
+    let mut count = 0;
+    gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+
+    let supported = (0..count as GLuint).any(|i| {
+        let name = gl::GetStringi(gl::EXTENSIONS, i);
+        !name.is_null() &&
+            CStr::from_ptr(name as *const _).to_bytes() == b"GL_EXT_texture_filter_anisotropic"
+    });
+
+    if supported {
+        let mut max = 0.0;
+        gl::GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY_EXT, &mut max);
+        Some(max.min(MAX_ANISOTROPY))
+    } else {
+        None
     }
 }
 
 impl TexType {
-    pub fn tiling(&self) -> i32 {
+    /// Every role a theme can provide.
+    pub const ROLES: [TexType; 7] = [
+        TexType::Wall,
+        TexType::AltWall,
+        TexType::Floor,
+        TexType::Ceiling,
+        TexType::Sprite,
+        TexType::Smiley,
+        TexType::Logo
+    ];
+
+    /// The roles without a default image.
+    pub fn required(&self) -> bool {
+        match *self {
+            TexType::Smiley |
+            TexType::Logo => false,
+            _ => true
+        }
+    }
+
+    /// Name of the role in theme manifests.
+    pub fn role(&self) -> &'static str {
         match *self {
-            TexType::Rat |
-            TexType::Brick |
-            TexType::Thing => 1,
-            TexType::Ceiling |
-            TexType::Floor => 4,
-            _ => panic!()
+            TexType::Sprite => "sprite",
+            TexType::Wall => "wall",
+            TexType::AltWall => "alt-wall",
+            TexType::Ceiling => "ceiling",
+            TexType::Floor => "floor",
+            TexType::Smiley => "smiley",
+            TexType::Logo => "logo"
         }
     }
+
+    pub fn from_role(role: &str) -> Option<TexType> {
+        TexType::ROLES.iter().find(|t| t.role() == role).cloned()
+    }
 }
@@ -0,0 +1,308 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+
+use image::{self, DynamicImage};
+
+use texture::{TextureArray, TexType, TexSettings, Filter};
+use procedural::{Pattern, Params};
+use assets::Assets;
+use error::Error;
+
+const MANIFEST: &str = "theme.txt";
+
+/// Themes embedded in the binary.
+pub const BUILTIN: [&str; 3] = ["classic", "hedge", "office"];
+// prefix of generated images, like `procedural:brick`
+const PROCEDURAL: &str = "procedural:";
+
+// where the images of a theme are looked up
+#[derive(Debug)]
+enum Location {
+    // `themes/<name>` in the assets, embedded or overridden
+    Assets(String),
+    // any directory given in the command line
+    Dir(PathBuf)
+}
+
+/// Where the image of a role comes from.
+#[derive(Debug)]
+pub enum Source {
+    File(String),
+    Procedural(Pattern, Params)
+}
+
+/// What a theme says about one role.
+#[derive(Debug)]
+pub struct Role {
+    pub source: Source,
+    pub settings: TexSettings
+}
+
+/// A set of images for every `TexType` role, described by a manifest like:
+///
+/// ```text
+/// # role = image [option=value...]
+/// wall = brick.bmp
+/// floor = floor.bmp tiling=4
+/// ceiling = procedural:stucco color=e0e0d0 seed=3 tiling=4
+/// sprite = rats.png frames=4 views=3 filter=trilinear
+/// ```
+///
+/// Image paths are relative to the manifest. Images that fail
+/// to load are replaced by a procedural texture.
+#[derive(Debug)]
+pub struct Theme {
+    location: Location,
+    roles: HashMap<TexType, Role>
+}
+
+impl Role {
+    fn new(source: Source) -> Role {
+        Role {
+            source: source,
+            settings: TexSettings {
+                tiling: 1,
+                chroma_key: None,
+                filter: Filter::Nearest,
+                frames: 1,
+                views: 1
+            }
+        }
+    }
+
+    fn set(&mut self, option: &str) -> Result<(), String> {
+        let mut kv = option.splitn(2, '=');
+        let key = kv.next().unwrap_or("");
+        let value = kv.next().ok_or_else(|| format!("expected `key=value`, got `{}`", option))?;
+
+        match key {
+            "tiling" => {
+                self.settings.tiling = value.parse()
+                    .ok()
+                    .filter(|&t| t > 0)
+                    .ok_or_else(|| format!("bad tiling `{}`", value))?;
+            }
+            "chroma" => self.settings.chroma_key = Some(parse_color(value)?),
+            "filter" => {
+                self.settings.filter = Filter::parse(value)
+                    .ok_or_else(|| format!("bad filter `{}`", value))?;
+            }
+            "frames" => {
+                self.settings.frames = value.parse()
+                    .ok()
+                    .filter(|&f| f > 0)
+                    .ok_or_else(|| format!("bad frames `{}`", value))?;
+            }
+            "views" => {
+                self.settings.views = value.parse()
+                    .ok()
+                    .filter(|&v| v == 1 || v == 3)
+                    .ok_or_else(|| format!("bad views `{}`, expected 1 or 3", value))?;
+            }
+            // the rest only make sense for generated images
+            _ => {
+                let params = match self.source {
+                    Source::Procedural(_, ref mut params) => params,
+                    Source::File(_) if is_procedural_option(key) => {
+                        return Err(format!("`{}` is only for procedural images", key))
+                    }
+                    Source::File(_) => return Err(format!("unknown option `{}`", key))
+                };
+                match key {
+                    "seed" => {
+                        params.seed = value.parse()
+                            .map_err(|_| format!("bad seed `{}`", value))?;
+                    }
+                    "size" => {
+                        params.size = value.parse()
+                            .ok()
+                            .filter(|s: &u32| s.is_power_of_two() && *s >= 8)
+                            .ok_or_else(|| format!("bad size `{}`", value))?;
+                    }
+                    "color" => params.color = parse_color(value)?,
+                    "accent" => params.accent = parse_color(value)?,
+                    _ => return Err(format!("unknown option `{}`", key))
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Theme {
+    /// `name` is either a theme in the assets, like `classic`,
+    /// or a directory with a `theme.txt` manifest.
+    pub fn load(assets: &Assets, name: &str) -> Result<Theme, Error> {
+        let dir = Path::new(name);
+        let manifest = dir.join(MANIFEST);
+
+        if manifest.is_file() {
+            let path = manifest.display().to_string();
+            let text = fs::read_to_string(&manifest)
+                .map_err(|err| Error::Io { path: path.clone(), err: err })?;
+            Theme::parse(Location::Dir(dir.to_path_buf()), &text, &path)
+        } else {
+            let base = format!("themes/{}", name);
+            let path = format!("{}/{}", base, MANIFEST);
+            let text = assets.read_to_string(&path)?;
+            Theme::parse(Location::Assets(base), &text, &path)
+        }
+    }
+
+    fn parse(location: Location, text: &str, path: &str) -> Result<Theme, Error> {
+        let error = |message: String| Error::Theme {
+            path: path.to_string(),
+            message: message
+        };
+
+        let mut roles = HashMap::new();
+
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut kv = line.splitn(2, '=');
+            let role = kv.next().unwrap_or("").trim();
+            let mut words = kv.next().unwrap_or("").split_whitespace();
+
+            let textype = TexType::from_role(role)
+                .ok_or_else(|| error(format!("line {}: unknown role `{}`", n + 1, role)))?;
+            let image = words.next()
+                .ok_or_else(|| error(format!("line {}: missing image for `{}`", n + 1, role)))?;
+
+            let source = if image.starts_with(PROCEDURAL) {
+                let name = &image[PROCEDURAL.len()..];
+                let pattern = Pattern::parse(name)
+                    .ok_or_else(|| error(format!("line {}: unknown pattern `{}`", n + 1, name)))?;
+                Source::Procedural(pattern, pattern.default_params())
+            } else {
+                Source::File(image.to_string())
+            };
+
+            let mut spec = Role::new(source);
+            for option in words {
+                spec.set(option).map_err(|m| error(format!("line {}: {}", n + 1, m)))?;
+            }
+
+            roles.insert(textype, spec);
+        }
+
+        for textype in TexType::ROLES.iter().filter(|t| t.required()) {
+            if !roles.contains_key(textype) {
+                return Err(error(format!("missing role `{}`", textype.role())));
+            }
+        }
+
+        Ok(Theme {
+            location: location,
+            roles: roles
+        })
+    }
+
+    /// Uploads the images of every role as layers of a texture array.
+    /// `filter`, if given, overrides the one of every role.
+    pub unsafe fn load_textures(&self,
+                                assets: &Assets,
+                                filter: Option<Filter>) -> Result<TextureArray, Error> {
+        let mut images = Vec::new();
+
+        for textype in TexType::ROLES.iter() {
+            let pattern = fallback(*textype);
+            let default = Role::new(Source::Procedural(pattern, pattern.default_params()));
+            // optional roles are generated when missing
+            let role = self.roles.get(textype).unwrap_or(&default);
+
+            let img = match role.source {
+                Source::File(ref file) => {
+                    // only a missing file falls back, broken ones are errors
+                    match self.image(assets, file) {
+                        Ok(img) => img,
+                        Err(Error::Io { ref err, .. }) if err.kind() == io::ErrorKind::NotFound => {
+                            println!("Missing {}, using a procedural texture instead.", file);
+                            pattern.generate(&pattern.default_params())
+                        }
+                        Err(err) => return Err(err)
+                    }
+                }
+                Source::Procedural(pattern, ref params) => pattern.generate(params)
+            };
+
+            let mut settings = role.settings;
+            if let Some(filter) = filter {
+                settings.filter = filter;
+            }
+
+            images.push((*textype, img, settings));
+        }
+
+        let textures = TextureArray::new(&images, 0);
+        textures.bind();
+        Ok(textures)
+    }
+
+    fn image(&self, assets: &Assets, file: &str) -> Result<DynamicImage, Error> {
+        match self.location {
+            Location::Assets(ref base) => assets.image(&join(base, file)),
+            Location::Dir(ref dir) => {
+                let path = dir.join(file);
+                if !path.is_file() {
+                    return Err(Error::Io {
+                        path: path.display().to_string(),
+                        err: io::Error::new(io::ErrorKind::NotFound, "no such file")
+                    });
+                }
+                image::open(&path).map_err(|err| Error::Image {
+                    path: path.display().to_string(),
+                    err: err
+                })
+            }
+        }
+    }
+}
+
+// what to use when the image of a role is missing or fails to load
+fn fallback(textype: TexType) -> Pattern {
+    match textype {
+        TexType::Wall |
+        TexType::AltWall => Pattern::Brick,
+        TexType::Floor => Pattern::Tiles,
+        TexType::Ceiling => Pattern::Stucco,
+        TexType::Sprite => Pattern::Checker,
+        TexType::Smiley => Pattern::Smiley,
+        TexType::Logo => Pattern::Logo
+    }
+}
+
+fn is_procedural_option(key: &str) -> bool {
+    ["seed", "size", "color", "accent"].contains(&key)
+}
+
+// hex RGB color, like `00ff00`
+fn parse_color(value: &str) -> Result<[u8; 3], String> {
+    let channel = |i: usize| {
+        value.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok())
+    };
+    match (value.len(), channel(0), channel(2), channel(4)) {
+        (6, Some(r), Some(g), Some(b)) => Ok([r, g, b]),
+        _ => Err(format!("bad color `{}`", value))
+    }
+}
+
+// joins asset names resolving `..`, since embedded
+// assets are looked up by their exact name
+fn join(base: &str, file: &str) -> String {
+    let mut parts: Vec<&str> = base.split('/').collect();
+    for part in file.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => { parts.pop(); }
+            _ => parts.push(part)
+        }
+    }
+    parts.join("/")
+}
@@ -11,11 +11,15 @@ pub enum Direction {
     West
 }
 
+#[derive(Clone)]
 pub struct Walker<'a> {
     maze: &'a Maze,
     pub direction: Direction,
     pub i: usize,
-    pub j: usize
+    pub j: usize,
+    // the heading before the last step, the one it entered the previous
+    // cell with, to know the way back across junctions
+    came_from: Direction
 }
 
 impl Direction {
@@ -28,6 +32,27 @@ impl Direction {
         }
     }
 
+    /// A quarter turn counterclockwise, seen from above.
+    pub fn left(&self) -> Direction {
+        match *self {
+            Direction::North => Direction::West,
+            Direction::East => Direction::North,
+            Direction::South => Direction::East,
+            Direction::West => Direction::South
+        }
+    }
+
+    /// The direction closest to `v`, on the floor plane.
+    pub fn nearest(v: Vector3<f32>) -> Direction {
+        if v.x.abs() > v.z.abs() {
+            if v.x > 0.0 { Direction::East } else { Direction::West }
+        } else if v.z > 0.0 {
+            Direction::South
+        } else {
+            Direction::North
+        }
+    }
+
     pub fn to_vec(&self) -> Vector3<f32> {
         match *self {
             Direction::North => vec3(0.0, 0.0, -1.0),
@@ -50,7 +75,8 @@ impl<'a> Walker<'a> {
             maze: maze,
             direction: direction,
             i: i,
-            j: j
+            j: j,
+            came_from: direction
         }
     }
 
@@ -73,29 +99,123 @@ impl<'a> Walker<'a> {
     }
 
     pub fn next(&mut self) {
-        let mut directions: Vec<Direction> =
-            vec![Direction::North,
-                 Direction::East,
-                 Direction::South,
-                 Direction::West]
-            .into_iter()
-            .filter(|d| self.open(d))
-            .collect();
+        let mut directions = self.options();
 
         directions.sort_unstable_by_key(|_| rand::random::<u8>());
 
         for d in &directions {
             if self.open(d) && (self.direction != d.opposite() ||
                                 directions.len() == 1) {
-                match *d {
-                    Direction::North => self.i -= 1,
-                    Direction::East => self.j += 1,
-                    Direction::South => self.i += 1,
-                    Direction::West => self.j -= 1
-                }
-                self.direction = *d;
+                self.step(*d);
                 return;
             }
         }
     }
+
+    /// Goes back to the previous cell, facing the way it came from.
+    pub fn reverse(&mut self) {
+        let back = self.direction.opposite();
+        self.step(back);
+    }
+
+    /// Turns around and heads for the cell before the previous one, the way
+    /// it entered the previous cell, even if it turned there. Picks another
+    /// way if that one is walled, as after a teleport.
+    pub fn go_back(&mut self) {
+        let back = self.came_from.opposite();
+        self.reverse();
+        if self.options().contains(&back) {
+            self.step(back);
+        } else {
+            self.next();
+        }
+    }
+
+    /// Every direction without a wall.
+    pub fn options(&self) -> Vec<Direction> {
+        [Direction::North, Direction::East, Direction::South, Direction::West]
+            .iter()
+            .filter(|d| self.open(d))
+            .cloned()
+            .collect()
+    }
+
+    /// The cell one step towards `direction`, walls aside.
+    pub fn ahead(&self, direction: Direction) -> (usize, usize) {
+        match direction {
+            Direction::North => (self.i - 1, self.j),
+            Direction::East => (self.i, self.j + 1),
+            Direction::South => (self.i + 1, self.j),
+            Direction::West => (self.i, self.j - 1)
+        }
+    }
+
+    /// Moves to the next cell towards `direction`, which must be open.
+    pub fn step(&mut self, direction: Direction) {
+        let (i, j) = self.ahead(direction);
+        self.i = i;
+        self.j = j;
+        self.came_from = self.direction;
+        self.direction = direction;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maze::Maze;
+    use super::{Direction, Walker};
+
+    // a crossing at (1, 1):
+    //       (0,1)
+    // (1,0) (1,1) (1,2)
+    //       (2,1)
+    fn crossing() -> Maze {
+        let mut maze = Maze::closed(3, 3);
+        maze.connect((1, 1), (0, 1));
+        maze.connect((1, 1), (1, 0));
+        maze.connect((1, 1), (1, 2));
+        maze.connect((1, 1), (2, 1));
+        maze
+    }
+
+    #[test]
+    fn go_back_goes_straight_back() {
+        let maze = crossing();
+        let mut walker = Walker::new(&maze, 1, 0);
+        walker.step(Direction::East);
+        walker.step(Direction::East);
+
+        walker.go_back();
+
+        assert_eq!(walker.pos(), (1, 0));
+        assert_eq!(walker.direction, Direction::West);
+    }
+
+    #[test]
+    fn go_back_undoes_a_turn_at_a_junction() {
+        let maze = crossing();
+        // in from the west, then south at the crossing
+        let mut walker = Walker::new(&maze, 1, 0);
+        walker.step(Direction::East);
+        walker.step(Direction::South);
+
+        walker.go_back();
+
+        // not on across the crossing to the north
+        assert_eq!(walker.pos(), (1, 0));
+        assert_eq!(walker.direction, Direction::West);
+    }
+
+    #[test]
+    fn go_back_takes_another_way_when_walled() {
+        let maze = crossing();
+        // as after a teleport, it never came from the west of (1, 0)
+        let mut walker = Walker::new(&maze, 1, 0);
+        walker.step(Direction::East);
+
+        walker.go_back();
+
+        assert_eq!(walker.pos(), (1, 1));
+        assert_eq!(walker.direction, Direction::East);
+    }
 }
@@ -1,13 +1,12 @@
 use std::mem;
 use std::ptr;
-use std::collections::HashMap;
 use std::ffi::CStr;
 
 use gl;
 use gl::types::*;
 use cgmath::{Matrix4, Deg, Vector3};
 
-use texture::{Texture, TexType};
+use texture::{self, TextureArray, TexType};
 use shader::Shader;
 
 // texture coordinates are weird because somehow
@@ -33,7 +32,6 @@ pub struct Wall {
 
 #[derive(Debug)]
 pub struct WallRenderer {
-    last_textype: TexType,
     vao: GLuint
 }
 
@@ -78,21 +76,23 @@ impl WallRenderer {
         gl::EnableVertexAttribArray(1);
 
         WallRenderer {
-            last_textype: TexType::Other,
             vao: vao
         }
     }
 
-    pub unsafe fn set_up(&self, shader_program: &Shader) {
+    pub unsafe fn set_up(&self, shader_program: &Shader, textures: &TextureArray) {
         gl::BindVertexArray(self.vao);
 
-        shader_program.set_bool(c_str!("rat"), false);
+        shader_program.set_bool(c_str!("billboard"), false);
         shader_program.set_bool(c_str!("shaded"), false);
+        shader_program.set_int(c_str!("tex"), textures.number as i32);
+        // once here rather than for every wall, sprites leave it changed
+        texture::whole_image(shader_program);
     }
 
-    pub unsafe fn draw(&mut self,
+    pub unsafe fn draw(&self,
                        shader_program: &Shader,
-                       textures: &HashMap<TexType, Texture>,
+                       textures: &TextureArray,
                        wall: &Wall) {
         let model = Matrix4::from_translation(wall.pos) *
                     Matrix4::from_angle_y(Deg(wall.angle_y)) *
@@ -100,14 +100,8 @@ impl WallRenderer {
 
         shader_program.set_mat4(c_str!("model"), model);
 
-        // only change uniforms if texture changed
-        if self.last_textype != wall.textype {
-            let tex = textures[&wall.textype].number as i32;
-            shader_program.set_int(c_str!("tex"), tex);
-            shader_program.set_int(c_str!("tiling"), wall.textype.tiling());
-
-            self.last_textype = wall.textype;
-        }
+        // just a layer index, walls can come in any order
+        textures.select(shader_program, wall.textype);
 
         gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, ptr::null());
     }